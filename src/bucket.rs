@@ -1,9 +1,17 @@
 use std::fmt::{Debug, Formatter};
+use std::hash::{Hash, Hasher};
 use std::marker::PhantomData;
-use serde::{Deserialize, Deserializer};
-use crate::de::{BucketDeserializer, BucketVisitor};
+use std::ops::{Deref, DerefMut};
+use serde::{Deserialize, Deserializer, Serialize, Serializer};
+use serde::de::{DeserializeOwned, DeserializeSeed};
+use crate::de::{BucketBuffer, BucketConfig, BucketDeserializer, BucketTarget, BucketVisitor};
 use crate::debug::debug_nodes;
-use crate::node::BucketNode;
+use crate::limits::BucketLimits;
+use crate::node::{BucketNode, canonicalize, eq_numeric, eq_unordered, validate};
+use crate::segment::BucketSegment;
+use crate::ser::BucketSerializer;
+use crate::path::PathError;
+use crate::view::BucketRef;
 
 /// A Serde Bucket.
 ///
@@ -12,16 +20,18 @@ use crate::node::BucketNode;
 /// `Bucket` implements `serde::Deserialize` in-order to parse data,
 /// and has two methods to extract data:
 /// - [`deserialize_into`] - takes ownership of the owned/heap values (via `mem::swap`)
-/// and replaces them with null-like marker values.
-/// - [`deserialize_into_clone`[ - clones the owned/heap values which allows for
-/// multiple deserializations on the same bucket.
+///   and replaces them with null-like marker values.
+/// - [`deserialize_into_clone`] - borrows the owned/heap values (cloning them if the type
+///   needs ownership), which allows for multiple deserializations on the same bucket.
+///   This only needs a shared reference, so can be called from multiple threads at once.
 ///
 /// [`deserialize_into`]: Bucket::deserialize_into
 /// [`deserialize_into_clone`]: Bucket::deserialize_into_clone
 ///
 /// This type also implements `debug`, but the output is, well, not very good.
 pub struct Bucket<'a> {
-    pub(crate) inner: Vec<BucketNode<'a>>
+    pub(crate) inner: Vec<BucketNode<'a>>,
+    pub(crate) config: BucketConfig,
 }
 
 impl<'a> Debug for Bucket<'a> {
@@ -30,8 +40,33 @@ impl<'a> Debug for Bucket<'a> {
     }
 }
 
-/*
+/// Buckets are equal if their nodes are - so they hold the same value, captured with the same types.
+/// A captured struct doesn't equal the map it serializes as (see [`eq_unordered`](Bucket::eq_unordered)).
+///
+/// Owned and borrowed strings (and bytes) are equal, but numbers of different widths aren't
+/// (see [`eq_numeric`](Bucket::eq_numeric)). Floats are compared bitwise, so `NaN` equals itself
+/// and `0.0` doesn't equal `-0.0` - this keeps `Eq` and `Hash` consistent.
+impl<'a> PartialEq for Bucket<'a> {
+    fn eq(&self, other: &Self) -> bool {
+        self.inner == other.inner
+    }
+}
+
+impl<'a> Eq for Bucket<'a> {}
 
+impl<'a> Hash for Bucket<'a> {
+    fn hash<H: Hasher>(&self, state: &mut H) {
+        self.inner.hash(state)
+    }
+}
+
+/// An Owned Serde Bucket.
+///
+/// A [`Bucket`] that doesn't borrow from it's input - strings and bytes are always copied.
+/// This implements `serde::de::DeserializeOwned`, so can be parsed from readers, and can
+/// be stored for as long as needed.
+///
+/// `OwnedBucket` derefs into a `Bucket<'static>`, for access to it's other methods.
 pub struct OwnedBucket {
     inner: Bucket<'static>
 }
@@ -41,21 +76,88 @@ impl Debug for OwnedBucket {
         debug_nodes(&self.inner.inner, f)
     }
 }
-*/
+
+impl PartialEq for OwnedBucket {
+    fn eq(&self, other: &Self) -> bool {
+        self.inner == other.inner
+    }
+}
+
+impl Eq for OwnedBucket {}
+
+impl Hash for OwnedBucket {
+    fn hash<H: Hasher>(&self, state: &mut H) {
+        self.inner.hash(state)
+    }
+}
 
 impl<'a> Bucket<'a> {
+    pub(crate) fn new(inner: Vec<BucketNode<'a>>) -> Self {
+        Self {
+            inner,
+            config: BucketConfig::default(),
+        }
+    }
+
+    /// an empty bucket, with space for `capacity` nodes - for capturing into with a [`BucketSeed`]
+    pub fn with_capacity(capacity: usize) -> Self {
+        Self::new(Vec::with_capacity(capacity))
+    }
+
+    /// remove the bucket's value, keeping it's allocation (and settings)
+    pub fn clear(&mut self) {
+        self.inner.clear();
+    }
+
+    /// a `DeserializeSeed` that captures into this bucket, replacing it's value
+    pub fn seed(&mut self) -> BucketSeed<'_, 'a> {
+        BucketSeed {
+            bucket: self,
+            limits: BucketLimits::default(),
+        }
+    }
+
+    /// track the path of the value being deserialized, adding it to any errors
+    ///
+    /// Errors read like `invalid type: string "x", expected u32 at items[4].price`, or have
+    /// their path set for a `BucketError`. This is off by default, as it costs a copy of each map key read.
+    pub fn set_track_path(&mut self, track: bool) {
+        self.config.track_path = track;
+    }
+
+    /// coerce values into the primitive being deserialized, for data captured as strings
+    ///
+    /// Driven by the type being deserialized, this:
+    /// - parses strings into numbers (`"42"`), bools (`"true"`/`"1"`, `"false"`/`"0"`) and chars (`"c"`)
+    /// - converts integers between widths, failing if the value doesn't fit
+    /// - converts integers into floats
+    ///
+    /// Off by default.
+    pub fn set_coerce(&mut self, coerce: bool) {
+        self.config.coerce = coerce;
+    }
+
+    /// if the bucket was captured from a human-readable format
+    ///
+    /// Deserializing from the bucket reports the same, so types with a compact form (like `IpAddr`)
+    /// read back the representation they were captured in. `to_bucket` is human-readable.
+    pub fn is_human_readable(&self) -> bool {
+        self.config.human_readable
+    }
+
+    /// override whether the bucket is human-readable, for deserializing from it (and capturing with it's `serializer`)
+    pub fn set_human_readable(&mut self, human_readable: bool) {
+        self.config.human_readable = human_readable;
+    }
+
     /// deserialize into (clone method)
     ///
-    /// This function deserializes into the given type, cloning owned data (Vec, String)
+    /// This function deserializes into the given type, borrowing owned data (Vec, String)
+    /// from the bucket - types that need ownership will clone it.
     /// - `T` as `Deserialize`
     /// - `E` as a `serde::de::Error`
-    pub fn deserialize_into_clone<T: Deserialize<'a>, E: serde::de::Error>(&'a mut self) -> Result<T, E> {
-        T::deserialize(&mut BucketDeserializer {
-            buffer: &mut self.inner,
-            cursor: 0,
-            error: PhantomData::<E>::default(),
-            clone: true,
-        })
+    pub fn deserialize_into_clone<'r, T: Deserialize<'r>, E: serde::de::Error>(&'r self) -> Result<T, E> {
+        T::deserialize(&mut BucketDeserializer::new(BucketBuffer::Ref(&self.inner), self.config))
     }
 
     /// deserialize into (clone method)
@@ -63,47 +165,259 @@ impl<'a> Bucket<'a> {
     /// This function deserializes into the given type, taking/replacing owned data (Vec, String)
     /// - `T` as `Deserialize`
     /// - `E` as a `serde::de::Error`
-    pub fn deserialize_into<T: Deserialize<'a>, E: serde::de::Error>(&'a mut self) -> Result<T, E> {
-        T::deserialize(&mut BucketDeserializer {
-            buffer: &mut self.inner,
-            cursor: 0,
-            error: PhantomData::<E>::default(),
-            clone: false,
-        })
+    pub fn deserialize_into<T: Deserialize<'a>, E: serde::de::Error>(&mut self) -> Result<T, E> {
+        T::deserialize(&mut BucketDeserializer::new(BucketBuffer::Mut(&mut self.inner), self.config))
     }
 
     #[cfg(feature="deserializer")]
     /// get a 'deserializer' for custom deserialization
-    pub fn deserializer<E: serde::de::Error>(&'a mut self) -> BucketDeserializer<'a, E> {
-        BucketDeserializer {
-            buffer: &mut self.inner,
-            cursor: 0,
-            error: PhantomData::<E>::default(),
-            clone: false,
+    pub fn deserializer<E: serde::de::Error>(&mut self) -> BucketDeserializer<'_, 'a, E> {
+        BucketDeserializer::new(BucketBuffer::Mut(&mut self.inner), self.config)
+    }
+
+    #[cfg(feature="deserializer")]
+    /// get a 'deserializer' for custom deserialization, borrowing from a shared bucket
+    pub fn shared_deserializer<E: serde::de::Error>(&self) -> BucketDeserializer<'_, '_, E> {
+        BucketDeserializer::new(BucketBuffer::Ref(&self.inner), self.config)
+    }
+
+    /// compare with another bucket, ignoring the width of numbers
+    ///
+    /// Unlike `==`, an `u8` from one format equals an `u64` (or `i32`) of the same value
+    /// from another, and floats are compared by value (`f32` widened to `f64`, so `NaN` never
+    /// equals anything). Integers never equal floats.
+    pub fn eq_numeric(&self, other: &Bucket<'_>) -> bool {
+        eq_numeric(&self.inner, &other.inner)
+    }
+
+    /// sort the entries of every map (including struct fields) by their key
+    ///
+    /// Afterwards, buckets holding maps with the same entries in a different order are equal
+    /// (and hash the same). Keys of different kinds are ordered by kind, others by their value.
+    pub fn canonicalize(&mut self) {
+        canonicalize(&mut self.inner);
+    }
+
+    /// compare with another bucket, ignoring the order of map entries
+    ///
+    /// Values are compared as the data they serialize as, so a captured struct equals a map
+    /// with the same (string) keys, and a tuple struct the same sequence. Numbers still need the
+    /// same width (see [`eq_numeric`](Bucket::eq_numeric)).
+    ///
+    /// This copies (without cloning strings) and sorts the nodes of both buckets -
+    /// when comparing many times, [`canonicalize`](Bucket::canonicalize) them once instead.
+    pub fn eq_unordered(&self, other: &Bucket<'_>) -> bool {
+        eq_unordered(&self.inner, &other.inner)
+    }
+
+    /// check the bucket is well-formed
+    ///
+    /// Buckets built by this crate are always well-formed, unless a capture failed part-way
+    /// (such as an error from a bucket's `serializer`). Malformed buckets never panic,
+    /// but may fail to deserialize (or serialize) in unexpected places.
+    /// - `E` as a `serde::de::Error`
+    pub fn validate<E: serde::de::Error>(&self) -> Result<(), E> {
+        validate(&self.inner)
+            .map_err(|(index, fault)| fault.error(Some(index)))
+    }
+
+    /// get a segment of the bucket's (root) value, for indexing into sequences and maps
+    pub fn segment(&mut self) -> BucketSegment<'_, 'a> {
+        BucketSegment {
+            slice: &mut self.inner,
+            config: self.config,
         }
     }
+
+    /// get a read-only view of the bucket's (root) value, for inspecting it without deserializing
+    pub fn view(&self) -> BucketRef<'_> {
+        BucketRef::item(&self.inner, self.config)
+    }
+
+    /// get a read-only view of the value at a path (see [`BucketPath`](crate::BucketPath) for the syntax)
+    pub fn get(&self, path: &str) -> Result<BucketRef<'_>, PathError> {
+        self.view().get(path)
+    }
+
+    /// deserialize the value at a path into the given type (clone method)
+    ///
+    /// A missing path (or invalid syntax) is returned as a custom error of `E`.
+    /// - `T` as `Deserialize`
+    /// - `E` as a `serde::de::Error`
+    pub fn get_into<'r, T: Deserialize<'r>, E: serde::de::Error>(&'r self, path: &str) -> Result<T, E> {
+        self.view().get_into(path)
+    }
+
+    /// convert into a bucket that doesn't borrow it's input, copying any borrowed strings/bytes
+    pub fn into_owned(self) -> Bucket<'static> {
+        Bucket {
+            inner: self.inner.into_iter().map(BucketNode::into_owned).collect(),
+            config: self.config,
+        }
+    }
+
+    #[cfg(feature="serializer")]
+    /// get a 'serializer' that captures a value into this bucket, replacing it's contents
+    pub fn serializer<E: serde::ser::Error>(&mut self) -> BucketSerializer<'_, 'a, E> {
+        self.inner.clear();
+        BucketSerializer {
+            target: &mut self.inner,
+            human_readable: self.config.human_readable,
+            error: PhantomData,
+        }
+    }
+}
+
+/// serialize into a bucket
+///
+/// This function captures any `Serialize` value into a new `Bucket`, which can then be
+/// deserialized into another type without going through a data format.
+/// - `T` as `Serialize`
+/// - `E` as a `serde::ser::Error`
+pub fn to_bucket<'a, T: Serialize + ?Sized, E: serde::ser::Error>(value: &T) -> Result<Bucket<'a>, E> {
+    let mut nodes = Vec::new();
+    value.serialize(&mut BucketSerializer {
+        target: &mut nodes,
+        human_readable: true,
+        error: PhantomData::<E>,
+    })?;
+    Ok(Bucket::new(nodes))
 }
 
 impl<'de> Deserialize<'de> for Bucket<'de> {
     fn deserialize<D>(deserializer: D) -> Result<Self, D::Error> where D: Deserializer<'de> {
-        let mut nodes = Vec::new();
-        deserializer.deserialize_any(&mut BucketVisitor {
-            target: &mut nodes,
-            owned: false,
-        })?;
-        Ok(Self { inner: nodes })
+        let mut bucket = Bucket::new(Vec::new());
+        bucket.seed().deserialize(deserializer)?;
+        Ok(bucket)
+    }
+}
+
+/// A `DeserializeSeed` that captures into an existing `Bucket`
+///
+/// The bucket is cleared first, so it's allocation can be reused for many captures - and
+/// is left empty if the capture fails. Settings of the bucket (like [`set_coerce`](Bucket::set_coerce)) are kept.
+/// Made with [`Bucket::seed`].
+pub struct BucketSeed<'b, 'a> {
+    bucket: &'b mut Bucket<'a>,
+    limits: BucketLimits,
+}
+
+impl<'b, 'a> BucketSeed<'b, 'a> {
+    /// fail the capture if it goes over any of the `limits`
+    pub fn with_limits(mut self, limits: BucketLimits) -> Self {
+        self.limits = limits;
+        self
+    }
+}
+
+impl<'b, 'de> DeserializeSeed<'de> for BucketSeed<'b, 'de> {
+    type Value = ();
+
+    fn deserialize<D>(self, deserializer: D) -> Result<Self::Value, D::Error> where D: Deserializer<'de> {
+        self.bucket.clear();
+        self.bucket.set_human_readable(deserializer.is_human_readable());
+        let result = deserializer.deserialize_any(&mut BucketVisitor::new(BucketTarget::Borrowed(&mut self.bucket.inner), self.limits));
+        if result.is_err() {
+            // rather than leave a partial capture
+            self.bucket.clear();
+        }
+        result
+    }
+}
+
+/// A `DeserializeSeed` that captures into an existing `OwnedBucket`
+///
+/// Like [`BucketSeed`], but copies strings and bytes - so it can be reused for inputs
+/// that don't outlive the bucket (such as a buffer read into over and over).
+/// Made with [`OwnedBucket::seed`].
+pub struct OwnedBucketSeed<'b> {
+    bucket: &'b mut OwnedBucket,
+    limits: BucketLimits,
+}
+
+impl<'b> OwnedBucketSeed<'b> {
+    /// fail the capture if it goes over any of the `limits`
+    pub fn with_limits(mut self, limits: BucketLimits) -> Self {
+        self.limits = limits;
+        self
+    }
+}
+
+impl<'de, 'b> DeserializeSeed<'de> for OwnedBucketSeed<'b> {
+    type Value = ();
+
+    fn deserialize<D>(self, deserializer: D) -> Result<Self::Value, D::Error> where D: Deserializer<'de> {
+        let bucket = &mut self.bucket.inner;
+        bucket.clear();
+        bucket.set_human_readable(deserializer.is_human_readable());
+        let result = deserializer.deserialize_any(&mut BucketVisitor::new(BucketTarget::Owned(&mut bucket.inner), self.limits));
+        if result.is_err() {
+            bucket.clear();
+        }
+        result
+    }
+}
+
+impl<'de> Deserialize<'de> for OwnedBucket {
+    fn deserialize<D>(deserializer: D) -> Result<Self, D::Error> where D: Deserializer<'de> {
+        let mut bucket = OwnedBucket { inner: Bucket::new(Vec::new()) };
+        bucket.seed().deserialize(deserializer)?;
+        Ok(bucket)
     }
 }
 
-/*
 impl OwnedBucket {
+    /// deserialize into (consuming method)
+    ///
+    /// This function deserializes into the given type, moving owned data (Vec, String) out of the bucket
+    /// - `T` as `DeserializeOwned`
+    /// - `E` as a `serde::de::Error`
     pub fn deserialize_into<T: DeserializeOwned, E: serde::de::Error>(mut self) -> Result<T, E> {
-        let mut deser = BucketDeserializer {
-            buffer: &mut self.inner.inner,
-            cursor: 0,
-            error: PhantomData::<E>::default(),
-            clone: false,
-        };
-        T::deserialize(deser)
-    }
-}*/
\ No newline at end of file
+        T::deserialize(&mut BucketDeserializer::new(BucketBuffer::Mut(&mut self.inner.inner), self.inner.config))
+    }
+
+    /// a `DeserializeSeed` that captures into this bucket, replacing it's value
+    pub fn seed(&mut self) -> OwnedBucketSeed<'_> {
+        OwnedBucketSeed {
+            bucket: self,
+            limits: BucketLimits::default(),
+        }
+    }
+
+    /// get the inner `Bucket`
+    pub fn into_inner(self) -> Bucket<'static> {
+        self.inner
+    }
+}
+
+impl<'a> From<Bucket<'a>> for OwnedBucket {
+    fn from(bucket: Bucket<'a>) -> Self {
+        Self { inner: bucket.into_owned() }
+    }
+}
+
+impl From<OwnedBucket> for Bucket<'static> {
+    fn from(bucket: OwnedBucket) -> Self {
+        bucket.inner
+    }
+}
+
+impl Deref for OwnedBucket {
+    type Target = Bucket<'static>;
+
+    fn deref(&self) -> &Self::Target {
+        &self.inner
+    }
+}
+
+impl DerefMut for OwnedBucket {
+    fn deref_mut(&mut self) -> &mut Self::Target {
+        &mut self.inner
+    }
+}
+
+impl Serialize for OwnedBucket {
+    fn serialize<S>(&self, serializer: S) -> Result<S::Ok, S::Error> where S: Serializer {
+        self.inner.serialize(serializer)
+    }
+}
\ No newline at end of file
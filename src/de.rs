@@ -1,152 +1,1035 @@
 use std::fmt::Formatter;
 use std::marker::PhantomData;
 use std::mem;
-use serde::de::{DeserializeSeed, Error, MapAccess, SeqAccess, Visitor};
-use serde::{Deserializer, forward_to_deserialize_any};
-use crate::node::BucketNode;
+use std::str::FromStr;
+use serde::de::{DeserializeSeed, EnumAccess, Error, Expected, IntoDeserializer, MapAccess, SeqAccess, Unexpected, VariantAccess, Visitor};
+use serde::de::value::BorrowedStrDeserializer;
+use serde::{Deserialize, Deserializer};
+use crate::{Bucket, OwnedBucket};
+use crate::fault::{Fault, attach_path, locate};
+use crate::limits::{BucketLimits, LimitExceeded};
+use crate::node::{BucketNode, VariantKind, value_len};
+use crate::path::{PathSegment, key_segment};
 
-pub struct BucketDeserializer<'de, E> where E: serde::de::Error {
-    pub(crate) buffer: &'de mut [BucketNode<'de>],
+/// the nodes a `BucketDeserializer` reads from
+pub(crate) enum BucketBuffer<'r, 'de> {
+    /// exclusive access - owned values (String, Vec) are taken, leaving `Consumed` markers
+    Mut(&'r mut [BucketNode<'de>]),
+    /// shared access - owned values are borrowed
+    Ref(&'de [BucketNode<'de>]),
+}
+
+/// settings for deserializing from a bucket
+#[derive(Copy, Clone)]
+pub(crate) struct BucketConfig {
+    /// track the path of the value being deserialized, and add it to errors
+    pub(crate) track_path: bool,
+    /// if the bucket was captured from a human-readable format (or serializer)
+    pub(crate) human_readable: bool,
+    /// coerce strings (and other widths of number) into the primitive being deserialized
+    pub(crate) coerce: bool,
+}
+
+impl Default for BucketConfig {
+    fn default() -> Self {
+        Self {
+            track_path: false,
+            human_readable: true,
+            coerce: false,
+        }
+    }
+}
+
+pub struct BucketDeserializer<'r, 'de, E> where E: serde::de::Error {
+    pub(crate) buffer: BucketBuffer<'r, 'de>,
     pub(crate) cursor: usize,
+    pub(crate) config: BucketConfig,
+    /// the path to the value under the cursor (when tracked)
+    pub(crate) path: Vec<PathSegment>,
+    /// if the current error already has it's path
+    pub(crate) located: bool,
     pub(crate) error: PhantomData<E>,
-    pub(crate) clone: bool,
 }
 
+impl<'r, 'de, E> BucketDeserializer<'r, 'de, E> where E: serde::de::Error {
+    pub(crate) fn new(buffer: BucketBuffer<'r, 'de>, config: BucketConfig) -> Self {
+        Self {
+            buffer,
+            cursor: 0,
+            config,
+            path: Vec::new(),
+            located: false,
+            error: PhantomData,
+        }
+    }
 
-impl<'x, 'de, E> Deserializer<'de> for &'x mut BucketDeserializer<'de, E> where E: serde::de::Error {
-    type Error = E;
+    /// track the path of the value being deserialized, adding it to any errors
+    ///
+    /// Errors read like `invalid type: string "x", expected u32 at items[4].price`, or have
+    /// their path set for a `BucketError`. This costs a copy of each map key read.
+    pub fn set_track_path(&mut self, track: bool) {
+        self.config.track_path = track;
+    }
+
+    /// coerce values into the primitive being deserialized, for data captured as strings
+    ///
+    /// See [`Bucket::set_coerce`] for the conversions.
+    pub fn set_coerce(&mut self, coerce: bool) {
+        self.config.coerce = coerce;
+    }
+
+    /// override whether the bucket is reported as human-readable (see [`Bucket::set_human_readable`])
+    pub fn set_human_readable(&mut self, human_readable: bool) {
+        self.config.human_readable = human_readable;
+    }
+
+    fn nodes(&self) -> &[BucketNode<'de>] {
+        match &self.buffer {
+            BucketBuffer::Mut(buffer) => buffer,
+            BucketBuffer::Ref(buffer) => buffer,
+        }
+    }
 
-    fn deserialize_any<V>(mut self, visitor: V) -> Result<V::Value, Self::Error> where V: Visitor<'de> {
-        let node=  &mut self.buffer[self.cursor];
+    /// the node under the cursor
+    fn peek(&self) -> Option<&BucketNode<'de>> {
+        self.nodes().get(self.cursor)
+    }
+
+    /// read the node under the cursor, moving past it
+    fn next(&mut self) -> Result<BucketNode<'de>, E> {
+        let node = match &mut self.buffer {
+            BucketBuffer::Mut(buffer) => match buffer.get_mut(self.cursor) {
+                Some(node @ (BucketNode::String(_) | BucketNode::Bytes(_))) => mem::take(node),
+                Some(node) => node.clone(),
+                None => return Err(Fault::OutOfBounds.error(Some(self.cursor))),
+            },
+            BucketBuffer::Ref(buffer) => {
+                let buffer: &'de [BucketNode<'de>] = buffer;
+                match buffer.get(self.cursor) {
+                    Some(node) => node.borrowed(),
+                    None => return Err(Fault::OutOfBounds.error(Some(self.cursor))),
+                }
+            }
+        };
         self.cursor += 1;
-        match node {
-            BucketNode::Consumed => return Err(E::custom("value as already been consumed")),
-            BucketNode::Unsized => return Err(E::custom("invalid value - no size data")),
+        Ok(node)
+    }
+
+    /// add the location of the value starting at node `start` to an error from reading it
+    fn located<T>(&self, start: usize, result: Result<T, E>) -> Result<T, E> {
+        result.map_err(|error| locate(error, start, self.config.track_path.then_some(&self.path[..])))
+    }
+
+    /// step into a child value, if the path is tracked
+    fn enter(&mut self, segment: impl FnOnce(Option<&BucketNode<'de>>) -> PathSegment) {
+        if self.config.track_path {
+            let segment = segment(self.peek());
+            self.path.push(segment);
+        }
+    }
+
+    /// step out of a child value, adding the path to an error from it (or it's children)
+    fn leave<T>(&mut self, result: Result<T, E>) -> Result<T, E> {
+        if !self.config.track_path {
+            return result;
+        }
+        let result = match result {
+            Ok(value) => {
+                // any earlier error was handled by the visitor
+                self.located = false;
+                Ok(value)
+            }
+            Err(error) if !self.located => {
+                self.located = true;
+                Err(attach_path(error, &self.path))
+            }
+            Err(error) => Err(error),
+        };
+        self.path.pop();
+        result
+    }
+
+    /// add the path to an error from the root value, when it starts below the bucket's root
+    pub(crate) fn finish<T>(&mut self, result: Result<T, E>) -> Result<T, E> {
+        match result {
+            Err(error) if self.config.track_path && !self.located => Err(attach_path(error, &self.path)),
+            result => result,
+        }
+    }
+
+    /// the end of a container spanning `span` nodes after the cursor
+    fn end(&self, span: usize) -> Result<usize, E> {
+        match self.cursor.checked_add(span) {
+            Some(end) if end <= self.nodes().len() => Ok(end),
+            _ => Err(Fault::Malformed("container runs past the end of the bucket").error(Some(self.cursor - 1))),
+        }
+    }
+
+    /// move to the end of a container, skipping any elements left unread
+    fn close(&mut self, end: usize) -> Result<(), E> {
+        if self.cursor > end {
+            return Err(Fault::Malformed("container elements run past it's span").error(Some(end)));
+        }
+        self.cursor = end;
+        Ok(())
+    }
+}
+
+impl<'r, 'de, E> BucketDeserializer<'r, 'de, E> where E: serde::de::Error {
+    fn read_any<V>(&mut self, visitor: V) -> Result<V::Value, E> where V: Visitor<'de> {
+        match self.next()? {
+            BucketNode::Consumed => Err(Fault::Consumed.error(Some(self.cursor - 1))),
+            BucketNode::Unsized => Err(Fault::MissingSize.error(Some(self.cursor - 1))),
 
             BucketNode::Unit => visitor.visit_unit(),
             BucketNode::None => visitor.visit_none(),
 
-            BucketNode::Bool(val) => visitor.visit_bool(*val),
-            BucketNode::U8(val) => visitor.visit_u8(*val),
-            BucketNode::I8(val) => visitor.visit_i8(*val),
-            BucketNode::U16(val) => visitor.visit_u16(*val),
-            BucketNode::I16(val) => visitor.visit_i16(*val),
-            BucketNode::U32(val) => visitor.visit_u32(*val),
-            BucketNode::I32(val) => visitor.visit_i32(*val),
-            BucketNode::U64(val) => visitor.visit_u64(*val),
-            BucketNode::I64(val) => visitor.visit_i64(*val),
-            BucketNode::U128(val) => visitor.visit_u128(*val),
-            BucketNode::I128(val) => visitor.visit_i128(*val),
-            BucketNode::F32(val) => visitor.visit_f32(*val),
-            BucketNode::F64(val) => visitor.visit_f64(*val),
-            BucketNode::Char(val) => visitor.visit_char(*val),
-
-            BucketNode::Bytes(bytes) if self.clone => {
-                visitor.visit_byte_buf(bytes.clone())
-            },
-            val @ BucketNode::Bytes(_) => {
-                let mut drain = BucketNode::Consumed;
-                mem::swap(val, &mut drain);
-                let BucketNode::Bytes(bytes) = drain else {
-                    unreachable!()
+            BucketNode::Bool(val) => visitor.visit_bool(val),
+            BucketNode::U8(val) => visitor.visit_u8(val),
+            BucketNode::I8(val) => visitor.visit_i8(val),
+            BucketNode::U16(val) => visitor.visit_u16(val),
+            BucketNode::I16(val) => visitor.visit_i16(val),
+            BucketNode::U32(val) => visitor.visit_u32(val),
+            BucketNode::I32(val) => visitor.visit_i32(val),
+            BucketNode::U64(val) => visitor.visit_u64(val),
+            BucketNode::I64(val) => visitor.visit_i64(val),
+            BucketNode::U128(val) => visitor.visit_u128(val),
+            BucketNode::I128(val) => visitor.visit_i128(val),
+            BucketNode::F32(val) => visitor.visit_f32(val),
+            BucketNode::F64(val) => visitor.visit_f64(val),
+            BucketNode::Char(val) => visitor.visit_char(val),
+
+            BucketNode::Bytes(bytes) => visitor.visit_byte_buf(bytes),
+            BucketNode::BytesRef(val) => visitor.visit_borrowed_bytes(val),
+
+            BucketNode::String(string) => visitor.visit_string(string),
+            BucketNode::StringRef(val) => visitor.visit_borrowed_str(val),
+
+            BucketNode::Some => visitor.visit_some(self),
+
+            BucketNode::NewType => visitor.visit_newtype_struct(self),
+            // the visitor must read every element of a container, as with other formats
+            BucketNode::Seq(size, span) => {
+                let end = self.end(span)?;
+                let mut seq = BucketSeqDeserializer {
+                    size,
+                    index: 0,
+                    end,
+                    bucket: &mut *self,
                 };
-                visitor.visit_byte_buf(bytes)
+                let value = visitor.visit_seq(&mut seq)?;
+                if seq.size > 0 {
+                    return Err(E::invalid_length(size, &ExpectedLen(seq.index, "sequence")));
+                }
+                self.close(end)?;
+                Ok(value)
+            }
+            BucketNode::Map(size, span) => {
+                let end = self.end(span)?;
+                let mut map = BucketMapDeserializer {
+                    size,
+                    end,
+                    bucket: &mut *self,
+                    pending: false,
+                };
+                let value = visitor.visit_map(&mut map)?;
+                if map.size > 0 {
+                    return Err(E::invalid_length(size, &ExpectedLen(size - map.size, "map")));
+                }
+                self.close(end)?;
+                Ok(value)
             }
-            BucketNode::BytesRef(val) => visitor.visit_borrowed_bytes(val),
 
-            BucketNode::String(string) if self.clone => {
-                visitor.visit_string(string.clone())
+            BucketNode::UnitStruct(_) => visitor.visit_unit(),
+            // names are only replayed to typed methods - here they're transparent, as with other formats
+            BucketNode::NewTypeStruct(_) => self.read_any(visitor),
+            BucketNode::TupleStruct(_) => match self.peek() {
+                Some(BucketNode::Seq(..)) => self.deserialize_any(visitor),
+                _ => Err(Fault::Malformed("expected tuple fields").error(Some(self.cursor))),
+            },
+            BucketNode::Struct(_) => match self.peek() {
+                Some(BucketNode::Map(..)) => self.deserialize_any(visitor),
+                _ => Err(Fault::Malformed("expected struct fields").error(Some(self.cursor))),
             },
-            val @ BucketNode::String(_) => {
+            BucketNode::Field(name) => visitor.visit_borrowed_str(name),
 
-                let mut drain = BucketNode::Consumed;
-                mem::swap(val, &mut drain);
-                let BucketNode::String(string) = drain else {
-                    unreachable!()
+            // variants are replayed externally tagged - unit variants as their name,
+            // others as a map of their name to the payload
+            BucketNode::Variant(_, _, variant, VariantKind::Unit) => visitor.visit_borrowed_str(variant),
+            BucketNode::Variant(_, _, variant, _) => self.read_variant_map(Some(variant), visitor),
+            BucketNode::Enum => {
+                let Some(identifier) = value_len(self.nodes(), self.cursor) else {
+                    return Err(Fault::OutOfBounds.error(Some(self.cursor)));
                 };
+                match self.nodes().get(self.cursor + identifier) {
+                    // captured from a unit variant
+                    Some(BucketNode::Unit) => {
+                        let value = self.read_any(visitor)?;
+                        self.cursor += 1;
+                        Ok(value)
+                    }
+                    _ => self.read_variant_map(None, visitor),
+                }
+            }
+        }
+    }
 
-                visitor.visit_string(string)
-            },
-            BucketNode::StringRef(val) => visitor.visit_borrowed_str(val),
+    /// replay a variant as a map with a single entry, of it's name (or identifier) to the payload
+    fn read_variant_map<V>(&mut self, variant: Option<&'static str>, visitor: V) -> Result<V::Value, E> where V: Visitor<'de> {
+        let start = self.cursor - 1;
+        let Some(len) = value_len(self.nodes(), start) else {
+            return Err(Fault::OutOfBounds.error(Some(start)));
+        };
+        let mut map = BucketVariantMapDeserializer {
+            bucket: &mut *self,
+            variant,
+            key: false,
+            pending: false,
+        };
+        let value = visitor.visit_map(&mut map)?;
+        if !map.key || map.pending {
+            return Err(E::invalid_length(1, &ExpectedLen(0, "map")));
+        }
+        self.close(start + len)?;
+        Ok(value)
+    }
 
-            BucketNode::Some => visitor.visit_some(self),
+    fn read_ignored_any<V>(&mut self, visitor: V) -> Result<V::Value, E> where V: Visitor<'de> {
+        let Some(len) = value_len(self.nodes(), self.cursor) else {
+            return Err(Fault::OutOfBounds.error(Some(self.cursor)));
+        };
+        self.cursor += len;
+        visitor.visit_unit()
+    }
 
-            BucketNode::NewType => visitor.visit_newtype_struct(self),
-            BucketNode::Seq(size) => visitor.visit_seq(BucketSeqDeserializer {
-                size: *size,
+    fn read_option<V>(&mut self, visitor: V) -> Result<V::Value, E> where V: Visitor<'de> {
+        match self.peek() {
+            Some(BucketNode::None | BucketNode::Unit) => {
+                self.cursor += 1;
+                visitor.visit_none()
+            }
+            Some(BucketNode::Some) => {
+                self.cursor += 1;
+                visitor.visit_some(self)
+            }
+            _ => visitor.visit_some(self),
+        }
+    }
+
+    fn read_enum<V>(&mut self, visitor: V) -> Result<V::Value, E> where V: Visitor<'de> {
+        // enums are externally tagged - either a unit variant as a string,
+        // or a map with a single key (the variant) and value (the payload)
+        self.skip_newtypes();
+        match self.peek() {
+            Some(BucketNode::String(_) | BucketNode::StringRef(_)) => visitor.visit_enum(BucketEnumDeserializer {
                 bucket: self,
+                variant: None,
+                payload: false,
+                segment: None,
             }),
-            BucketNode::Map(size) => visitor.visit_map(BucketMapDeserializer {
-                size: *size,
-                bucket: self,
-                pending: false,
-            })
+            Some(&BucketNode::Map(1, span)) => {
+                self.cursor += 1;
+                let end = self.end(span)?;
+                let value = visitor.visit_enum(BucketEnumDeserializer {
+                    bucket: &mut *self,
+                    variant: None,
+                    payload: true,
+                    segment: None,
+                })?;
+                self.close(end)?;
+                Ok(value)
+            }
+            Some(BucketNode::Map(..)) => Err(E::invalid_value(Unexpected::Map, &"map with a single key")),
+            // or a captured variant, replayed as it was
+            Some(&BucketNode::Variant(_, _, variant, kind)) => {
+                self.cursor += 1;
+                visitor.visit_enum(BucketEnumDeserializer {
+                    bucket: self,
+                    variant: Some(variant),
+                    payload: kind != VariantKind::Unit,
+                    segment: None,
+                })
+            }
+            Some(BucketNode::Enum) => {
+                self.cursor += 1;
+                visitor.visit_enum(BucketEnumDeserializer {
+                    bucket: self,
+                    variant: None,
+                    payload: true,
+                    segment: None,
+                })
+            }
+            _ => self.read_typed(|_| false, visitor),
+        }
+    }
+
+    /// move past any newtype markers - formats don't tell newtypes apart from what they contain
+    fn skip_newtypes(&mut self) {
+        while let Some(BucketNode::NewType | BucketNode::NewTypeStruct(_)) = self.peek() {
+            self.cursor += 1;
+        }
+    }
+
+    /// read a value if it's a node that `accept`s, otherwise fail with an `invalid_type`
+    fn read_typed<V>(&mut self, accept: fn(&BucketNode<'de>) -> bool, visitor: V) -> Result<V::Value, E> where V: Visitor<'de> {
+        self.skip_newtypes();
+        let start = self.cursor;
+        let result = match self.peek() {
+            // faults are reported by `read_any`
+            Some(BucketNode::Consumed | BucketNode::Unsized) | None => self.read_any(visitor),
+            Some(node) if accept(node) => self.read_any(visitor),
+            Some(node) => Err(E::invalid_type(unexpected(node), &visitor)),
+        };
+        self.located(start, result)
+    }
+
+    /// read a primitive, converting it from another node with `coerce` when enabled, otherwise as `read_typed`
+    fn read_coerced<V, T>(
+        &mut self,
+        coerce: fn(&BucketNode<'de>) -> Option<Option<T>>,
+        visit: fn(V, T) -> Result<V::Value, E>,
+        accept: fn(&BucketNode<'de>) -> bool,
+        visitor: V,
+    ) -> Result<V::Value, E> where V: Visitor<'de> {
+        if self.config.coerce {
+            self.skip_newtypes();
+            let start = self.cursor;
+            if let Some(node) = self.peek() {
+                match coerce(node) {
+                    Some(Some(value)) => {
+                        self.cursor += 1;
+                        let result = visit(visitor, value);
+                        return self.located(start, result);
+                    }
+                    Some(None) => return self.located(start, Err(E::invalid_value(unexpected(node), &visitor))),
+                    None => {}
+                }
+            }
+        }
+        self.read_typed(accept, visitor)
+    }
+
+    fn read_bytes<V>(&mut self, visitor: V) -> Result<V::Value, E> where V: Visitor<'de> {
+        self.skip_newtypes();
+        match self.peek() {
+            Some(BucketNode::String(_) | BucketNode::StringRef(_) | BucketNode::Field(_)) => match self.next()? {
+                BucketNode::String(string) => visitor.visit_byte_buf(string.into_bytes()),
+                BucketNode::StringRef(val) | BucketNode::Field(val) => visitor.visit_borrowed_bytes(val.as_bytes()),
+                _ => unreachable!(),
+            },
+            _ => self.read_typed(|node| matches!(node, BucketNode::Bytes(_) | BucketNode::BytesRef(_) | BucketNode::Seq(..)), visitor),
+        }
+    }
+
+    fn read_newtype_struct<V>(&mut self, visitor: V) -> Result<V::Value, E> where V: Visitor<'de> {
+        if let Some(BucketNode::NewType | BucketNode::NewTypeStruct(_)) = self.peek() {
+            self.cursor += 1;
+        }
+        visitor.visit_newtype_struct(self)
+    }
+}
+
+/// a number that nodes can be coerced into
+trait Coerce: FromStr {
+    fn from_u128(val: u128) -> Option<Self>;
+    fn from_i128(val: i128) -> Option<Self>;
+}
+
+macro_rules! coerce_integer {
+    ($($ty:ty)*) => {
+        $(
+            impl Coerce for $ty {
+                fn from_u128(val: u128) -> Option<Self> {
+                    val.try_into().ok()
+                }
+
+                fn from_i128(val: i128) -> Option<Self> {
+                    val.try_into().ok()
+                }
+            }
+        )*
+    };
+}
+
+macro_rules! coerce_float {
+    ($($ty:ty)*) => {
+        $(
+            impl Coerce for $ty {
+                fn from_u128(val: u128) -> Option<Self> {
+                    Some(val as $ty)
+                }
+
+                fn from_i128(val: i128) -> Option<Self> {
+                    Some(val as $ty)
+                }
+            }
+        )*
+    };
+}
+
+coerce_integer!(u8 u16 u32 u64 u128 i8 i16 i32 i64 i128);
+coerce_float!(f32 f64);
+
+// coercions - `None` if the node can't be coerced, `Some(None)` if it's value doesn't fit
+
+/// integers (checking the range) and strings into any number
+fn coerce_number<T: Coerce>(node: &BucketNode) -> Option<Option<T>> {
+    Some(match *node {
+        BucketNode::U8(val) => T::from_u128(val as u128),
+        BucketNode::U16(val) => T::from_u128(val as u128),
+        BucketNode::U32(val) => T::from_u128(val as u128),
+        BucketNode::U64(val) => T::from_u128(val as u128),
+        BucketNode::U128(val) => T::from_u128(val),
+        BucketNode::I8(val) => T::from_i128(val as i128),
+        BucketNode::I16(val) => T::from_i128(val as i128),
+        BucketNode::I32(val) => T::from_i128(val as i128),
+        BucketNode::I64(val) => T::from_i128(val as i128),
+        BucketNode::I128(val) => T::from_i128(val),
+        ref node => node.as_str()?.parse().ok(),
+    })
+}
+
+/// `"true"`/`"1"` and `"false"`/`"0"` into a bool
+fn coerce_bool(node: &BucketNode) -> Option<Option<bool>> {
+    Some(match node.as_str()? {
+        "true" | "1" => Some(true),
+        "false" | "0" => Some(false),
+        _ => None,
+    })
+}
+
+/// a single-character string into a char
+fn coerce_char(node: &BucketNode) -> Option<Option<char>> {
+    let mut chars = node.as_str()?.chars();
+    Some(match (chars.next(), chars.next()) {
+        (Some(val), None) => Some(val),
+        _ => None,
+    })
+}
+
+/// accepts any number, for the visitor to convert (or reject)
+fn is_number(node: &BucketNode) -> bool {
+    matches!(node,
+        BucketNode::U8(_) | BucketNode::U16(_) | BucketNode::U32(_) | BucketNode::U64(_) | BucketNode::U128(_) |
+        BucketNode::I8(_) | BucketNode::I16(_) | BucketNode::I32(_) | BucketNode::I64(_) | BucketNode::I128(_) |
+        BucketNode::F32(_) | BucketNode::F64(_)
+    )
+}
+
+fn is_string(node: &BucketNode) -> bool {
+    matches!(node, BucketNode::String(_) | BucketNode::StringRef(_) | BucketNode::Field(_) | BucketNode::Char(_))
+}
+
+fn is_identifier(node: &BucketNode) -> bool {
+    is_string(node) || matches!(node,
+        BucketNode::U8(_) | BucketNode::U16(_) | BucketNode::U32(_) | BucketNode::U64(_) |
+        BucketNode::Bytes(_) | BucketNode::BytesRef(_)
+    )
+}
+
+fn is_seq(node: &BucketNode) -> bool {
+    matches!(node, BucketNode::Seq(..) | BucketNode::TupleStruct(_))
+}
+
+fn is_map(node: &BucketNode) -> bool {
+    matches!(node, BucketNode::Map(..) | BucketNode::Struct(_))
+}
+
+/// the node as a `serde::de::Unexpected`, for `invalid_type` errors
+fn unexpected<'n>(node: &'n BucketNode) -> Unexpected<'n> {
+    match node {
+        BucketNode::Bool(val) => Unexpected::Bool(*val),
+        BucketNode::U8(val) => Unexpected::Unsigned(*val as u64),
+        BucketNode::U16(val) => Unexpected::Unsigned(*val as u64),
+        BucketNode::U32(val) => Unexpected::Unsigned(*val as u64),
+        BucketNode::U64(val) => Unexpected::Unsigned(*val),
+        BucketNode::U128(val) => u64::try_from(*val).map_or(Unexpected::Other("u128"), Unexpected::Unsigned),
+        BucketNode::I8(val) => Unexpected::Signed(*val as i64),
+        BucketNode::I16(val) => Unexpected::Signed(*val as i64),
+        BucketNode::I32(val) => Unexpected::Signed(*val as i64),
+        BucketNode::I64(val) => Unexpected::Signed(*val),
+        BucketNode::I128(val) => i64::try_from(*val).map_or(Unexpected::Other("i128"), Unexpected::Signed),
+        BucketNode::F32(val) => Unexpected::Float(*val as f64),
+        BucketNode::F64(val) => Unexpected::Float(*val),
+        BucketNode::Char(val) => Unexpected::Char(*val),
+        BucketNode::String(val) => Unexpected::Str(val),
+        BucketNode::StringRef(val) | BucketNode::Field(val) => Unexpected::Str(val),
+        BucketNode::Bytes(val) => Unexpected::Bytes(val),
+        BucketNode::BytesRef(val) => Unexpected::Bytes(val),
+        BucketNode::Unit | BucketNode::UnitStruct(_) => Unexpected::Unit,
+        BucketNode::None | BucketNode::Some => Unexpected::Option,
+        BucketNode::NewType | BucketNode::NewTypeStruct(_) => Unexpected::NewtypeStruct,
+        BucketNode::Seq(..) | BucketNode::TupleStruct(_) => Unexpected::Seq,
+        BucketNode::Map(..) | BucketNode::Struct(_) => Unexpected::Map,
+        BucketNode::Variant(_, _, _, VariantKind::Unit) => Unexpected::UnitVariant,
+        BucketNode::Variant(_, _, _, VariantKind::NewType) => Unexpected::NewtypeVariant,
+        BucketNode::Variant(_, _, _, VariantKind::Tuple) => Unexpected::TupleVariant,
+        BucketNode::Variant(_, _, _, VariantKind::Struct) => Unexpected::StructVariant,
+        BucketNode::Enum => Unexpected::Enum,
+        BucketNode::Consumed | BucketNode::Unsized => Unexpected::Other("invalid bucket node"),
+    }
+}
+
+/// the number of elements read from a sequence or map, for `invalid_length` errors
+struct ExpectedLen(usize, &'static str);
+
+impl Expected for ExpectedLen {
+    fn fmt(&self, formatter: &mut Formatter) -> std::fmt::Result {
+        if self.0 == 1 {
+            write!(formatter, "1 element in {}", self.1)
+        } else {
+            write!(formatter, "{} elements in {}", self.0, self.1)
         }
     }
+}
+
+/// deserializer methods reading the value with a method of `BucketDeserializer` (locating any error)
+macro_rules! deserialize_with {
+    ($($method:ident($($arg:ident: $ty:ty),*) => $read:ident)*) => {
+        $(
+            fn $method<V>(self, $($arg: $ty,)* visitor: V) -> Result<V::Value, Self::Error> where V: Visitor<'de> {
+                let start = self.cursor;
+                let result = self.$read(visitor);
+                self.located(start, result)
+            }
+        )*
+    };
+}
+
+/// deserializer methods reading the value if it's node is accepted, otherwise failing with an `invalid_type`
+macro_rules! deserialize_typed {
+    ($($method:ident($($arg:ident: $ty:ty),*) => $accept:expr;)*) => {
+        $(
+            fn $method<V>(self, $($arg: $ty,)* visitor: V) -> Result<V::Value, Self::Error> where V: Visitor<'de> {
+                let start = self.cursor;
+                let result = self.read_typed($accept, visitor);
+                self.located(start, result)
+            }
+        )*
+    };
+}
+
+/// deserializer methods reading a primitive, coercing other nodes into it when enabled
+macro_rules! deserialize_coerced {
+    ($($method:ident() => $coerce:expr, $visit:ident, $accept:expr;)*) => {
+        $(
+            fn $method<V>(self, visitor: V) -> Result<V::Value, Self::Error> where V: Visitor<'de> {
+                let start = self.cursor;
+                let result = self.read_coerced($coerce, V::$visit::<E>, $accept, visitor);
+                self.located(start, result)
+            }
+        )*
+    };
+}
+
+impl<'r, 'de, E> Deserializer<'de> for &mut BucketDeserializer<'r, 'de, E> where E: serde::de::Error {
+    type Error = E;
+
+    fn is_human_readable(&self) -> bool {
+        self.config.human_readable
+    }
 
-    forward_to_deserialize_any! {
-        bool i8 i16 i32 i64 i128 u8 u16 u32 u64 u128 f32 f64 char str string
-        bytes byte_buf unit unit_struct newtype_struct seq tuple
-        tuple_struct map struct enum identifier ignored_any
+    fn deserialize_any<V>(self, visitor: V) -> Result<V::Value, Self::Error> where V: Visitor<'de> {
+        let start = self.cursor;
+        let result = self.read_any(visitor);
+        self.located(start, result)
     }
 
-    fn deserialize_option<V>(self, visitor: V) -> Result<V::Value, Self::Error> where V: Visitor<'de> {
-        visitor.visit_some(self)
+    deserialize_coerced! {
+        deserialize_bool() => coerce_bool, visit_bool, |node| matches!(node, BucketNode::Bool(_));
+        deserialize_i8() => coerce_number, visit_i8, is_number;
+        deserialize_i16() => coerce_number, visit_i16, is_number;
+        deserialize_i32() => coerce_number, visit_i32, is_number;
+        deserialize_i64() => coerce_number, visit_i64, is_number;
+        deserialize_i128() => coerce_number, visit_i128, is_number;
+        deserialize_u8() => coerce_number, visit_u8, is_number;
+        deserialize_u16() => coerce_number, visit_u16, is_number;
+        deserialize_u32() => coerce_number, visit_u32, is_number;
+        deserialize_u64() => coerce_number, visit_u64, is_number;
+        deserialize_u128() => coerce_number, visit_u128, is_number;
+        deserialize_f32() => coerce_number, visit_f32, is_number;
+        deserialize_f64() => coerce_number, visit_f64, is_number;
+        deserialize_char() => coerce_char, visit_char, |node| matches!(node, BucketNode::Char(_) | BucketNode::String(_) | BucketNode::StringRef(_));
     }
+
+    deserialize_typed! {
+        deserialize_str() => is_string; deserialize_string() => is_string;
+        deserialize_unit() => |node| matches!(node, BucketNode::Unit | BucketNode::UnitStruct(_));
+        deserialize_unit_struct(_name: &'static str) => |node| matches!(node, BucketNode::Unit | BucketNode::UnitStruct(_));
+        deserialize_seq() => is_seq;
+        deserialize_tuple(_len: usize) => is_seq;
+        deserialize_tuple_struct(_name: &'static str, _len: usize) => is_seq;
+        deserialize_map() => is_map;
+        // structs can also be read from a sequence of their fields
+        deserialize_struct(_name: &'static str, _fields: &'static [&'static str]) => |node| is_map(node) || is_seq(node);
+        deserialize_identifier() => is_identifier;
+    }
+
+    deserialize_with! {
+        deserialize_bytes() => read_bytes
+        deserialize_byte_buf() => read_bytes
+        deserialize_newtype_struct(_name: &'static str) => read_newtype_struct
+        deserialize_ignored_any() => read_ignored_any
+        deserialize_option() => read_option
+        deserialize_enum(_name: &'static str, _variants: &'static [&'static str]) => read_enum
+    }
+}
+
+/// A Serde Deserializer that owns the nodes of a `Bucket`
+///
+/// Owned values (String, Vec) are moved out as they're deserialized,
+/// which allows a bucket to be passed by value to anything expecting a `Deserializer`.
+pub struct BucketIntoDeserializer<'de, E> where E: serde::de::Error {
+    pub(crate) nodes: Vec<BucketNode<'de>>,
+    pub(crate) config: BucketConfig,
+    pub(crate) error: PhantomData<E>,
 }
 
-struct BucketSeqDeserializer<'x, 'de, E> where E: serde::de::Error {
-    bucket: &'x mut BucketDeserializer<'de, E>,
+impl<'de, E> BucketIntoDeserializer<'de, E> where E: serde::de::Error {
+    fn deserializer(&mut self) -> BucketDeserializer<'_, 'de, E> {
+        BucketDeserializer::new(BucketBuffer::Mut(&mut self.nodes), self.config)
+    }
+}
+
+/// forward deserializer methods to the (borrowed) `BucketDeserializer`
+macro_rules! forward_to_bucket_deserializer {
+    ($($method:ident($($arg:ident: $ty:ty),*))*) => {
+        $(
+            fn $method<V>(mut self, $($arg: $ty,)* visitor: V) -> Result<V::Value, Self::Error> where V: Visitor<'de> {
+                self.deserializer().$method($($arg,)* visitor)
+            }
+        )*
+    };
+}
+
+impl<'de, E> Deserializer<'de> for BucketIntoDeserializer<'de, E> where E: serde::de::Error {
+    type Error = E;
+
+    fn is_human_readable(&self) -> bool {
+        self.config.human_readable
+    }
+
+    forward_to_bucket_deserializer! {
+        deserialize_any()
+        deserialize_bool() deserialize_char()
+        deserialize_i8() deserialize_i16() deserialize_i32() deserialize_i64() deserialize_i128()
+        deserialize_u8() deserialize_u16() deserialize_u32() deserialize_u64() deserialize_u128()
+        deserialize_f32() deserialize_f64()
+        deserialize_str() deserialize_string() deserialize_bytes() deserialize_byte_buf()
+        deserialize_option() deserialize_unit() deserialize_unit_struct(name: &'static str)
+        deserialize_newtype_struct(name: &'static str)
+        deserialize_seq() deserialize_tuple(len: usize) deserialize_tuple_struct(name: &'static str, len: usize)
+        deserialize_map() deserialize_struct(name: &'static str, fields: &'static [&'static str])
+        deserialize_enum(name: &'static str, variants: &'static [&'static str])
+        deserialize_identifier() deserialize_ignored_any()
+    }
+}
+
+impl<'de, E> IntoDeserializer<'de, E> for Bucket<'de> where E: serde::de::Error {
+    type Deserializer = BucketIntoDeserializer<'de, E>;
+
+    fn into_deserializer(self) -> Self::Deserializer {
+        BucketIntoDeserializer {
+            nodes: self.inner,
+            config: self.config,
+            error: PhantomData,
+        }
+    }
+}
+
+impl<'de, E> IntoDeserializer<'de, E> for OwnedBucket where E: serde::de::Error {
+    type Deserializer = BucketIntoDeserializer<'de, E>;
+
+    fn into_deserializer(self) -> Self::Deserializer {
+        let bucket = self.into_inner();
+        BucketIntoDeserializer {
+            nodes: bucket.inner,
+            config: bucket.config,
+            error: PhantomData,
+        }
+    }
+}
+
+struct BucketSeqDeserializer<'x, 'r, 'de, E> where E: serde::de::Error {
+    bucket: &'x mut BucketDeserializer<'r, 'de, E>,
     size: usize,
+    /// the index of the next element
+    index: usize,
+    /// the end of the sequence's span
+    end: usize,
 }
 
-impl<'x, 'de, E> SeqAccess<'de> for BucketSeqDeserializer<'x, 'de, E> where E: serde::de::Error {
+impl<'x, 'r, 'de, E> SeqAccess<'de> for BucketSeqDeserializer<'x, 'r, 'de, E> where E: serde::de::Error {
     type Error = E;
 
     fn next_element_seed<T>(&mut self, seed: T) -> Result<Option<T::Value>, Self::Error> where T: DeserializeSeed<'de> {
         if self.size == 0 {
             Ok(None)
+        } else if self.bucket.cursor >= self.end {
+            Err(Fault::Malformed("sequence has fewer elements than it's length").error(Some(self.bucket.cursor)))
         } else {
             self.size -= 1;
-            seed.deserialize(&mut *self.bucket)
-                .map(Some)
+            let index = self.index;
+            self.index += 1;
+            self.bucket.enter(|_| PathSegment::Index(index));
+            let value = seed.deserialize(&mut *self.bucket);
+            self.bucket.leave(value).map(Some)
         }
     }
 }
 
-struct BucketMapDeserializer<'x, 'de, E> where E: serde::de::Error {
-    bucket: &'x mut BucketDeserializer<'de, E>,
+struct BucketMapDeserializer<'x, 'r, 'de, E> where E: serde::de::Error {
+    bucket: &'x mut BucketDeserializer<'r, 'de, E>,
     size: usize,
+    /// the end of the map's span
+    end: usize,
     pending: bool,
 }
 
-impl<'x, 'de, E> MapAccess<'de> for BucketMapDeserializer<'x, 'de, E> where E: serde::de::Error {
+impl<'x, 'r, 'de, E> MapAccess<'de> for BucketMapDeserializer<'x, 'r, 'de, E> where E: serde::de::Error {
     type Error = E;
 
     fn next_key_seed<K>(&mut self, seed: K) -> Result<Option<K::Value>, Self::Error> where K: DeserializeSeed<'de> {
         if self.pending {
-           return Err(E::custom("out-of-order access - no value for previous key"));
+            return Err(Fault::OutOfOrder("no value for previous key").error(Some(self.bucket.cursor)));
         }
         if self.size == 0 {
             return Ok(None)
         }
+        if self.bucket.cursor >= self.end {
+            return Err(Fault::Malformed("map has fewer entries than it's length").error(Some(self.bucket.cursor)));
+        }
         self.pending = true;
-        seed.deserialize(&mut *self.bucket).map(Some)
+        // the key stays in the path until it's value has been read
+        self.bucket.enter(key_segment);
+        match seed.deserialize(&mut *self.bucket) {
+            Ok(key) => Ok(Some(key)),
+            error => self.bucket.leave(error).map(Some),
+        }
     }
 
     fn next_value_seed<V>(&mut self, seed: V) -> Result<V::Value, Self::Error> where V: DeserializeSeed<'de> {
         if !self.pending {
-            return Err(E::custom("out-of-order access - no key for this value"));
+            return Err(Fault::OutOfOrder("no key for this value").error(Some(self.bucket.cursor)));
         }
         self.pending = false;
         self.size -= 1;
-        seed.deserialize(&mut *self.bucket)
+        let value = seed.deserialize(&mut *self.bucket);
+        self.bucket.leave(value)
+    }
+}
+
+/// a captured variant, as a map with a single entry
+struct BucketVariantMapDeserializer<'x, 'r, 'de, E> where E: serde::de::Error {
+    bucket: &'x mut BucketDeserializer<'r, 'de, E>,
+    /// the variant name if known, otherwise it's read from the next node
+    variant: Option<&'static str>,
+    /// if the key has been read
+    key: bool,
+    pending: bool,
+}
+
+impl<'x, 'r, 'de, E> MapAccess<'de> for BucketVariantMapDeserializer<'x, 'r, 'de, E> where E: serde::de::Error {
+    type Error = E;
+
+    fn next_key_seed<K>(&mut self, seed: K) -> Result<Option<K::Value>, Self::Error> where K: DeserializeSeed<'de> {
+        if self.pending {
+            return Err(Fault::OutOfOrder("no value for previous key").error(Some(self.bucket.cursor)));
+        }
+        if self.key {
+            return Ok(None);
+        }
+        self.key = true;
+        self.pending = true;
+        let variant = self.variant;
+        self.bucket.enter(|node| match variant {
+            Some(name) => PathSegment::Key(name.to_owned()),
+            None => key_segment(node),
+        });
+        let key = match variant {
+            Some(name) => seed.deserialize(BorrowedStrDeserializer::new(name)),
+            None => seed.deserialize(&mut *self.bucket),
+        };
+        match key {
+            Ok(key) => Ok(Some(key)),
+            error => self.bucket.leave(error).map(Some),
+        }
+    }
+
+    fn next_value_seed<V>(&mut self, seed: V) -> Result<V::Value, Self::Error> where V: DeserializeSeed<'de> {
+        if !self.pending {
+            return Err(Fault::OutOfOrder("no key for this value").error(Some(self.bucket.cursor)));
+        }
+        self.pending = false;
+        let value = seed.deserialize(&mut *self.bucket);
+        self.bucket.leave(value)
+    }
+
+    fn size_hint(&self) -> Option<usize> {
+        Some(if self.key { 0 } else { 1 })
+    }
+}
+
+struct BucketEnumDeserializer<'x, 'r, 'de, E> where E: serde::de::Error {
+    bucket: &'x mut BucketDeserializer<'r, 'de, E>,
+    /// the variant name if known, otherwise it's read from the next node
+    variant: Option<&'static str>,
+    payload: bool,
+    /// the variant, for the path of the payload (when tracked)
+    segment: Option<PathSegment>,
+}
+
+impl<'x, 'r, 'de, E> BucketEnumDeserializer<'x, 'r, 'de, E> where E: serde::de::Error {
+    /// read the payload, with the variant in the path
+    fn read_payload<T>(self, read: impl FnOnce(&mut BucketDeserializer<'r, 'de, E>) -> Result<T, E>) -> Result<T, E> {
+        match self.segment {
+            Some(segment) => {
+                self.bucket.enter(|_| segment);
+                let value = read(self.bucket);
+                self.bucket.leave(value)
+            }
+            None => read(self.bucket),
+        }
+    }
+}
+
+impl<'x, 'r, 'de, E> EnumAccess<'de> for BucketEnumDeserializer<'x, 'r, 'de, E> where E: serde::de::Error {
+    type Error = E;
+    type Variant = Self;
+
+    fn variant_seed<V>(mut self, seed: V) -> Result<(V::Value, Self::Variant), Self::Error> where V: DeserializeSeed<'de> {
+        if self.bucket.config.track_path {
+            self.segment = Some(match self.variant {
+                Some(name) => PathSegment::Key(name.to_owned()),
+                None => key_segment(self.bucket.peek()),
+            });
+        }
+        let variant = match self.variant {
+            Some(name) => seed.deserialize(BorrowedStrDeserializer::new(name))?,
+            None => seed.deserialize(&mut *self.bucket)?,
+        };
+        Ok((variant, self))
     }
 }
 
+impl<'x, 'r, 'de, E> VariantAccess<'de> for BucketEnumDeserializer<'x, 'r, 'de, E> where E: serde::de::Error {
+    type Error = E;
+
+    fn unit_variant(self) -> Result<(), Self::Error> {
+        if self.payload {
+            self.read_payload(|bucket| <()>::deserialize(bucket))
+        } else {
+            Ok(())
+        }
+    }
+
+    fn newtype_variant_seed<T>(self, seed: T) -> Result<T::Value, Self::Error> where T: DeserializeSeed<'de> {
+        if self.payload {
+            self.read_payload(|bucket| seed.deserialize(bucket))
+        } else {
+            Err(E::invalid_type(Unexpected::UnitVariant, &"newtype variant"))
+        }
+    }
+
+    fn tuple_variant<V>(self, _len: usize, visitor: V) -> Result<V::Value, Self::Error> where V: Visitor<'de> {
+        if self.payload {
+            self.read_payload(|bucket| bucket.deserialize_any(visitor))
+        } else {
+            Err(E::invalid_type(Unexpected::UnitVariant, &"tuple variant"))
+        }
+    }
+
+    fn struct_variant<V>(self, _fields: &'static [&'static str], visitor: V) -> Result<V::Value, Self::Error> where V: Visitor<'de> {
+        if self.payload {
+            self.read_payload(|bucket| bucket.deserialize_any(visitor))
+        } else {
+            Err(E::invalid_type(Unexpected::UnitVariant, &"struct variant"))
+        }
+    }
+}
+
+/// the nodes a `BucketVisitor` captures into
+pub(crate) enum BucketTarget<'t, 'de> {
+    /// borrowing strings and bytes from the input, where it can
+    Borrowed(&'t mut Vec<BucketNode<'de>>),
+    /// copying strings and bytes, so the nodes outlive the input
+    Owned(&'t mut Vec<BucketNode<'static>>),
+}
+
 pub struct BucketVisitor<'t, 'de> {
-    pub(crate) target: &'t mut Vec<BucketNode<'de>>,
-    pub(crate) owned: bool,
+    pub(crate) target: BucketTarget<'t, 'de>,
+    pub(crate) limits: BucketLimits,
+    /// the number of values the visitor is inside
+    pub(crate) depth: usize,
+    /// the nodes, and bytes of strings, captured so far
+    pub(crate) nodes: usize,
+    pub(crate) bytes: usize,
+}
+
+impl<'t, 'de> BucketVisitor<'t, 'de> {
+    pub(crate) fn new(target: BucketTarget<'t, 'de>, limits: BucketLimits) -> Self {
+        Self {
+            target,
+            limits,
+            depth: 0,
+            nodes: 0,
+            bytes: 0,
+        }
+    }
+
+    fn push<E: Error>(&mut self, node: BucketNode<'de>) -> Result<(), E> {
+        if self.nodes >= self.limits.max_nodes {
+            return Err(E::custom(LimitExceeded::Nodes(self.limits.max_nodes)));
+        }
+        self.nodes += 1;
+        match &mut self.target {
+            BucketTarget::Borrowed(target) => target.push(node),
+            BucketTarget::Owned(target) => target.push(node.into_owned()),
+        }
+        Ok(())
+    }
+
+    /// the number of nodes in the target
+    fn len(&self) -> usize {
+        match &self.target {
+            BucketTarget::Borrowed(target) => target.len(),
+            BucketTarget::Owned(target) => target.len(),
+        }
+    }
+
+    /// set the size of the container at `position`, now that all of it's children are captured
+    fn close(&mut self, position: usize, container: fn(usize, usize) -> BucketNode<'static>, count: usize) {
+        let node = container(count, self.len() - position - 1);
+        match &mut self.target {
+            BucketTarget::Borrowed(target) => target[position] = node,
+            BucketTarget::Owned(target) => target[position] = node,
+        }
+    }
+
+    /// check a string (or bytes) of `len` bytes is within the limits, before it's copied
+    fn data<E: Error>(&mut self, len: usize) -> Result<(), E> {
+        if len > self.limits.max_string_len {
+            return Err(E::custom(LimitExceeded::StringLen(len, self.limits.max_string_len)));
+        }
+        match self.bytes.checked_add(len) {
+            Some(bytes) if bytes <= self.limits.max_total_bytes => {
+                self.bytes = bytes;
+                Ok(())
+            }
+            _ => Err(E::custom(LimitExceeded::TotalBytes(self.limits.max_total_bytes))),
+        }
+    }
+
+    /// capture a nested value with `visit`, within the depth limit
+    fn nested<E: Error>(&mut self, visit: impl FnOnce(&mut Self) -> Result<(), E>) -> Result<(), E> {
+        if self.depth >= self.limits.max_depth {
+            return Err(E::custom(LimitExceeded::Depth(self.limits.max_depth)));
+        }
+        self.depth += 1;
+        let result = visit(self);
+        self.depth -= 1;
+        result
+    }
 }
 
 impl<'x, 't, 'de> Visitor<'de> for &'x mut BucketVisitor<'t, 'de> {
@@ -157,160 +1040,153 @@ impl<'x, 't, 'de> Visitor<'de> for &'x mut BucketVisitor<'t, 'de> {
     }
 
     fn visit_bool<E>(self, v: bool) -> Result<Self::Value, E> where E: Error {
-        self.target.push(BucketNode::Bool(v));
-        Ok(())
+        self.push(BucketNode::Bool(v))
     }
 
     fn visit_i8<E>(self, v: i8) -> Result<Self::Value, E> where E: Error {
-        self.target.push(BucketNode::I8(v));
-        Ok(())
+        self.push(BucketNode::I8(v))
     }
 
     fn visit_i16<E>(self, v: i16) -> Result<Self::Value, E> where E: Error {
-        self.target.push(BucketNode::I16(v));
-        Ok(())
+        self.push(BucketNode::I16(v))
     }
 
     fn visit_i32<E>(self, v: i32) -> Result<Self::Value, E> where E: Error {
-        self.target.push(BucketNode::I32(v));
-        Ok(())
+        self.push(BucketNode::I32(v))
     }
 
     fn visit_i64<E>(self, v: i64) -> Result<Self::Value, E> where E: Error {
-        self.target.push(BucketNode::I64(v));
-        Ok(())
+        self.push(BucketNode::I64(v))
     }
 
     fn visit_i128<E>(self, v: i128) -> Result<Self::Value, E> where E: Error {
-        self.target.push(BucketNode::I128(v));
-        Ok(())
+        self.push(BucketNode::I128(v))
     }
 
     fn visit_u8<E>(self, v: u8) -> Result<Self::Value, E> where E: Error {
-        self.target.push(BucketNode::U8(v));
-        Ok(())
+        self.push(BucketNode::U8(v))
     }
 
     fn visit_u16<E>(self, v: u16) -> Result<Self::Value, E> where E: Error {
-        self.target.push(BucketNode::U16(v));
-        Ok(())
+        self.push(BucketNode::U16(v))
     }
 
     fn visit_u32<E>(self, v: u32) -> Result<Self::Value, E> where E: Error {
-        self.target.push(BucketNode::U32(v));
-        Ok(())
+        self.push(BucketNode::U32(v))
     }
 
     fn visit_u64<E>(self, v: u64) -> Result<Self::Value, E> where E: Error {
-        self.target.push(BucketNode::U64(v));
-        Ok(())
+        self.push(BucketNode::U64(v))
     }
 
     fn visit_u128<E>(self, v: u128) -> Result<Self::Value, E> where E: Error {
-        self.target.push(BucketNode::U128(v));
-        Ok(())
+        self.push(BucketNode::U128(v))
     }
 
     fn visit_f32<E>(self, v: f32) -> Result<Self::Value, E> where E: Error {
-        self.target.push(BucketNode::F32(v));
-        Ok(())
+        self.push(BucketNode::F32(v))
     }
 
     fn visit_f64<E>(self, v: f64) -> Result<Self::Value, E> where E: Error {
-        self.target.push(BucketNode::F64(v));
-        Ok(())
+        self.push(BucketNode::F64(v))
     }
 
     fn visit_char<E>(self, v: char) -> Result<Self::Value, E> where E: Error {
-        self.target.push(BucketNode::Char(v));
-        Ok(())
+        self.push(BucketNode::Char(v))
     }
 
     fn visit_str<E>(self, v: &str) -> Result<Self::Value, E> where E: Error {
-        self.target.push(BucketNode::String(v.to_owned()));
-        Ok(())
+        self.data(v.len())?;
+        self.push(BucketNode::String(v.to_owned()))
     }
 
     fn visit_borrowed_str<E>(self, v: &'de str) -> Result<Self::Value, E> where E: Error {
-        if self.owned {
-            self.target.push(BucketNode::String(v.to_owned()));
-        } else {
-            self.target.push(BucketNode::StringRef(v));
-        }
-        Ok(())
+        self.data(v.len())?;
+        self.push(BucketNode::StringRef(v))
     }
 
     fn visit_string<E>(self, v: String) -> Result<Self::Value, E> where E: Error {
-        self.target.push(BucketNode::String(v));
-        Ok(())
+        self.data(v.len())?;
+        self.push(BucketNode::String(v))
     }
 
     fn visit_bytes<E>(self, v: &[u8]) -> Result<Self::Value, E> where E: Error {
-        self.target.push(BucketNode::Bytes(v.to_vec()));
-        Ok(())
+        self.data(v.len())?;
+        self.push(BucketNode::Bytes(v.to_vec()))
     }
 
     fn visit_borrowed_bytes<E>(self, v: &'de [u8]) -> Result<Self::Value, E> where E: Error {
-        if self.owned {
-            self.target.push(BucketNode::Bytes(v.to_vec()));
-        } else {
-            self.target.push(BucketNode::BytesRef(v));
-        }
-        Ok(())
+        self.data(v.len())?;
+        self.push(BucketNode::BytesRef(v))
     }
 
     fn visit_byte_buf<E>(self, v: Vec<u8>) -> Result<Self::Value, E> where E: Error {
-        self.target.push(BucketNode::Bytes(v));
-        Ok(())
+        self.data(v.len())?;
+        self.push(BucketNode::Bytes(v))
     }
 
     fn visit_none<E>(self) -> Result<Self::Value, E> where E: Error {
-        self.target.push(BucketNode::None);
-        Ok(())
+        self.push(BucketNode::None)
     }
 
     fn visit_some<D>(self, deserializer: D) -> Result<Self::Value, D::Error> where D: Deserializer<'de> {
-        self.target.push(BucketNode::Some);
-        deserializer.deserialize_any(self)
+        self.push(BucketNode::Some)?;
+        self.nested(|visitor| deserializer.deserialize_any(visitor))
     }
 
     fn visit_unit<E>(self) -> Result<Self::Value, E> where E: Error {
-        self.target.push(BucketNode::Unit);
-        Ok(())
+        self.push(BucketNode::Unit)
     }
 
     fn visit_newtype_struct<D>(self, deserializer: D) -> Result<Self::Value, D::Error> where D: Deserializer<'de> {
-        self.target.push(BucketNode::NewType);
-        deserializer.deserialize_any(self)
+        self.push(BucketNode::NewType)?;
+        self.nested(|visitor| deserializer.deserialize_any(visitor))
+    }
+
+    fn visit_enum<A>(self, data: A) -> Result<Self::Value, A::Error> where A: EnumAccess<'de> {
+        // the payload kind isn't known up-front, so it is captured as a newtype -
+        // which self-describing formats will hand over as whatever they contain.
+        // for a unit variant they report it as one without reading a payload (the variant
+        // can't be asked again once it's been read), so it's captured as unit instead
+        self.push(BucketNode::Enum)?;
+        self.nested(|visitor| {
+            let ((), variant) = data.variant_seed(&mut *visitor)?;
+            let mut payload = PayloadSeed { visitor, read: false };
+            match variant.newtype_variant_seed(&mut payload) {
+                Err(error) if !payload.read && is_unit_variant(&error) => payload.visitor.push(BucketNode::Unit),
+                result => result,
+            }
+        })
     }
 
     fn visit_seq<A>(self, mut seq: A) -> Result<Self::Value, A::Error> where A: SeqAccess<'de> {
-        let position = self.target.len();
+        let position = self.len();
         let mut count = 0;
-        self.target.push(BucketNode::Unsized);
+        self.push(BucketNode::Unsized)?;
 
-        loop {
-            if seq.next_element_seed(&mut *self)?.is_some() {
+        self.nested(|visitor| {
+            while seq.next_element_seed(&mut *visitor)?.is_some() {
                 count += 1;
-            } else {
-                break
             }
-        }
-        self.target[position] = BucketNode::Seq(count);
+            Ok(())
+        })?;
+        self.close(position, BucketNode::Seq, count);
         Ok(())
     }
 
     fn visit_map<A>(self, mut map: A) -> Result<Self::Value, A::Error> where A: MapAccess<'de> {
-        let position = self.target.len();
+        let position = self.len();
         let mut count = 0;
-        self.target.push(BucketNode::Unsized);
-
-        while let Some(_) = map.next_key_seed(&mut *self)? {
-            let _ = map.next_value_seed(&mut *self)?;
-            count += 1;
-        }
+        self.push(BucketNode::Unsized)?;
 
-        self.target[position] = BucketNode::Map(count);
+        self.nested(|visitor| {
+            while map.next_key_seed(&mut *visitor)?.is_some() {
+                map.next_value_seed(&mut *visitor)?;
+                count += 1;
+            }
+            Ok(())
+        })?;
+        self.close(position, BucketNode::Map, count);
         Ok(())
     }
 }
@@ -321,4 +1197,24 @@ impl<'x, 't, 'de> DeserializeSeed<'de> for &'x mut BucketVisitor<'t, 'de> {
     fn deserialize<D>(self, deserializer: D) -> Result<Self::Value, D::Error> where D: Deserializer<'de> {
         deserializer.deserialize_any(self)
     }
-}
\ No newline at end of file
+}
+
+/// if an error is the one a variant gives when it's read as a newtype, but is a unit variant
+fn is_unit_variant<E: Error>(error: &E) -> bool {
+    error.to_string() == E::invalid_type(Unexpected::UnitVariant, &"newtype variant").to_string()
+}
+
+/// captures the payload of an enum, noting if the format read one
+struct PayloadSeed<'v, 't, 'de> {
+    visitor: &'v mut BucketVisitor<'t, 'de>,
+    read: bool,
+}
+
+impl<'s, 'v, 't, 'de> DeserializeSeed<'de> for &'s mut PayloadSeed<'v, 't, 'de> {
+    type Value = ();
+
+    fn deserialize<D>(self, deserializer: D) -> Result<Self::Value, D::Error> where D: Deserializer<'de> {
+        self.read = true;
+        deserializer.deserialize_any(&mut *self.visitor)
+    }
+}
@@ -1,19 +1,19 @@
 use std::fmt;
 use std::fmt::{Debug, Formatter, Write};
-use serde::ser::Error;
-use crate::node::BucketNode;
+use crate::node::{BucketNode, VariantKind};
 
 pub(crate) fn debug_nodes(nodes: &[BucketNode], fmt: &mut Formatter) -> fmt::Result {
     let mut cursor = 0;
     while nodes.len() > cursor {
-        debug_node(&nodes, fmt, &mut cursor)?;
+        debug_node(nodes, fmt, &mut cursor)?;
     }
     Ok(())
 }
 
 pub(crate) fn debug_node(nodes: &[BucketNode], fmt: &mut Formatter, cursor: &mut usize) -> fmt::Result {
+    // malformed buckets are printed as far as possible, as returning an error would panic `format!`
     let Some(node) = nodes.get(*cursor) else {
-        return Err(fmt::Error::custom("out-of-bounds"))
+        return fmt.write_str("<missing>")
     };
     match node {
         BucketNode::Consumed | BucketNode::Unsized => fmt.write_char('_'),
@@ -45,29 +45,31 @@ pub(crate) fn debug_node(nodes: &[BucketNode], fmt: &mut Formatter, cursor: &mut
         BucketNode::Some => {
             *cursor += 1;
             fmt.write_str("Some(")?;
-            debug_node(&nodes, fmt, cursor)?;
+            debug_node(nodes, fmt, cursor)?;
             return fmt.write_str(")")
         }
-        BucketNode::Seq(seq) => {
+        BucketNode::Seq(seq, _) => {
             *cursor += 1;
             fmt.write_str("[")?;
 
             for i in 0..*seq {
                 if i != 0 { fmt.write_str(", ")?; }
-                debug_node(&nodes, fmt, cursor)?;
+                if *cursor >= nodes.len() { fmt.write_str("<missing>")?; break; }
+                debug_node(nodes, fmt, cursor)?;
             }
 
             return fmt.write_str("]")
         }
-        BucketNode::Map(map) => {
+        BucketNode::Map(map, _) => {
             *cursor += 1;
             fmt.write_str("{")?;
 
             for i in 0..*map {
                 if i != 0 { fmt.write_str(", ")?; }
-                debug_node(&nodes, fmt, cursor)?;
+                if *cursor >= nodes.len() { fmt.write_str("<missing>")?; break; }
+                debug_node(nodes, fmt, cursor)?;
                 fmt.write_str(": ")?;
-                debug_node(&nodes, fmt, cursor)?;
+                debug_node(nodes, fmt, cursor)?;
             }
 
             return fmt.write_str("}")
@@ -75,7 +77,45 @@ pub(crate) fn debug_node(nodes: &[BucketNode], fmt: &mut Formatter, cursor: &mut
         BucketNode::NewType => {
             *cursor += 1;
             fmt.write_str("(")?;
-            debug_node(&nodes, fmt, cursor)?;
+            debug_node(nodes, fmt, cursor)?;
+            return fmt.write_str(")")
+        }
+
+        BucketNode::UnitStruct(name) => fmt.write_str(name),
+        BucketNode::NewTypeStruct(name) => {
+            *cursor += 1;
+            fmt.write_str(name)?;
+            fmt.write_str("(")?;
+            debug_node(nodes, fmt, cursor)?;
+            return fmt.write_str(")")
+        }
+        BucketNode::TupleStruct(name) | BucketNode::Struct(name) => {
+            *cursor += 1;
+            fmt.write_str(name)?;
+            return debug_node(nodes, fmt, cursor)
+        }
+        BucketNode::Field(name) => fmt.write_str(name),
+
+        BucketNode::Variant(name, _, variant, kind) => {
+            *cursor += 1;
+            fmt.write_str(name)?;
+            fmt.write_str("::")?;
+            fmt.write_str(variant)?;
+            return match kind {
+                VariantKind::Unit => Ok(()),
+                VariantKind::NewType => {
+                    fmt.write_str("(")?;
+                    debug_node(nodes, fmt, cursor)?;
+                    fmt.write_str(")")
+                }
+                VariantKind::Tuple | VariantKind::Struct => debug_node(nodes, fmt, cursor),
+            }
+        }
+        BucketNode::Enum => {
+            *cursor += 1;
+            debug_node(nodes, fmt, cursor)?;
+            fmt.write_str("(")?;
+            debug_node(nodes, fmt, cursor)?;
             return fmt.write_str(")")
         }
 
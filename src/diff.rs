@@ -0,0 +1,268 @@
+use std::collections::HashMap;
+use std::slice;
+use std::vec;
+use serde::{Serialize, Serializer};
+use serde::ser::SerializeStruct;
+use crate::bucket::{Bucket, to_bucket};
+use crate::node::{BucketNode, container, plain};
+use crate::path::{BucketPath, PathSegment, key_segment};
+use crate::view::BucketRef;
+
+/// The differences between two buckets, as returned by [`diff`]
+///
+/// Changes are listed in the order of the first bucket, with added entries after the others
+/// of their map (or sequence). Serializes as a sequence of changes, like
+/// `{"kind": "changed", "path": "a.b[1]", "old": 1, "new": 2}`.
+#[derive(Debug, PartialEq, Eq)]
+pub struct BucketDiff<'r> {
+    pub(crate) changes: Vec<BucketChange<'r>>,
+}
+
+/// A single change in a [`BucketDiff`]
+///
+/// Values borrow their strings (and bytes) from the buckets that were compared.
+#[derive(Debug, PartialEq, Eq)]
+pub enum BucketChange<'r> {
+    /// a map entry or sequence element only in the second bucket
+    Added { path: BucketPath, value: Bucket<'r> },
+    /// a map entry or sequence element only in the first bucket
+    Removed { path: BucketPath, value: Bucket<'r> },
+    /// a value that is different in the second bucket
+    Changed { path: BucketPath, old: Bucket<'r>, new: Bucket<'r> },
+}
+
+/// compare two buckets, listing the paths that were added, removed or changed
+///
+/// Maps are compared by key (ignoring the order of entries), and sequences by index -
+/// so an element inserted at the front changes every element after it.
+/// Structs are compared as the maps they serialize as, so a captured struct can be compared
+/// with the same value from a data format. Values of different kinds (or structs of different
+/// names) are changed as a whole.
+pub fn diff<'r>(a: &'r Bucket<'_>, b: &'r Bucket<'_>) -> BucketDiff<'r> {
+    let mut diff = BucketDiff { changes: Vec::new() };
+    let mut path = Vec::new();
+    diff_into(a.view(), b.view(), &mut path, &mut diff.changes);
+    diff
+}
+
+/// compare two `Serialize` values, by capturing them into buckets (see [`diff`])
+/// - `E` as a `serde::ser::Error`
+pub fn diff_values<T, U, E>(a: &T, b: &U) -> Result<BucketDiff<'static>, E>
+    where T: Serialize + ?Sized, U: Serialize + ?Sized, E: serde::ser::Error
+{
+    let a: Bucket<'static> = to_bucket::<_, E>(a)?;
+    let b: Bucket<'static> = to_bucket::<_, E>(b)?;
+    Ok(diff(&a, &b).into_owned())
+}
+
+/// a copy of a value, borrowing it's strings and bytes
+fn copy<'r>(value: BucketRef<'r>) -> Bucket<'r> {
+    let mut bucket = Bucket::new(value.slice.iter().map(BucketNode::borrowed).collect());
+    bucket.config = value.config;
+    bucket
+}
+
+fn path_of(path: &[PathSegment]) -> BucketPath {
+    BucketPath { segments: path.to_vec() }
+}
+
+fn diff_into<'r>(a: BucketRef<'r>, b: BucketRef<'r>, path: &mut Vec<PathSegment>, changes: &mut Vec<BucketChange<'r>>) {
+    if a == b {
+        return;
+    }
+    match (a.slice.first(), b.slice.first()) {
+        (None, _) => changes.push(BucketChange::Added { path: path_of(path), value: copy(b) }),
+        (_, None) => changes.push(BucketChange::Removed { path: path_of(path), value: copy(a) }),
+        // the same wrappers are looked through
+        (Some(BucketNode::Some), Some(BucketNode::Some))
+        | (Some(BucketNode::NewType), Some(BucketNode::NewType)) => {
+            diff_into(BucketRef::item(&a.slice[1..], a.config), BucketRef::item(&b.slice[1..], b.config), path, changes);
+        }
+        (Some(BucketNode::NewTypeStruct(a_name)), Some(BucketNode::NewTypeStruct(b_name))) if a_name == b_name => {
+            diff_into(BucketRef::item(&a.slice[1..], a.config), BucketRef::item(&b.slice[1..], b.config), path, changes);
+        }
+        // as are the names of structs, when they match (or only one value has a name)
+        (Some(BucketNode::NewTypeStruct(_)), Some(node)) if !matches!(node, BucketNode::NewTypeStruct(_)) => {
+            diff_into(BucketRef::item(&a.slice[1..], a.config), b, path, changes);
+        }
+        (Some(node), Some(BucketNode::NewTypeStruct(_))) if !matches!(node, BucketNode::NewTypeStruct(_)) => {
+            diff_into(a, BucketRef::item(&b.slice[1..], b.config), path, changes);
+        }
+        _ if container(a.slice) == 1 && container(b.slice) == 1 && a.slice[0] != b.slice[0] => {
+            changes.push(BucketChange::Changed { path: path_of(path), old: copy(a), new: copy(b) });
+        }
+        _ => match (a.as_map(), b.as_map(), a.as_seq(), b.as_seq()) {
+            (Some(a_map), Some(b_map), _, _) => {
+                let mut b_entries: HashMap<EntryKey, (usize, BucketRef)> = b_map.iter()
+                    .enumerate()
+                    .map(|(index, (key, value))| (EntryKey::of(key), (index, value)))
+                    .collect();
+                for (key, a_value) in a_map.iter() {
+                    path.push(key_segment(key.slice.first()));
+                    match b_entries.remove(&EntryKey::of(key)) {
+                        Some((_, b_value)) => diff_into(a_value, b_value, path, changes),
+                        None => changes.push(BucketChange::Removed { path: path_of(path), value: copy(a_value) }),
+                    }
+                    path.pop();
+                }
+                let mut added: Vec<_> = b_entries.into_iter().collect();
+                added.sort_by_key(|(_, (index, _))| *index);
+                for (key, (_, b_value)) in added {
+                    path.push(key.segment());
+                    changes.push(BucketChange::Added { path: path_of(path), value: copy(b_value) });
+                    path.pop();
+                }
+            }
+            (_, _, Some(a_seq), Some(b_seq)) => {
+                let mut a_items = a_seq.iter();
+                let mut b_items = b_seq.iter();
+                let mut index = 0;
+                loop {
+                    path.push(PathSegment::Index(index));
+                    match (a_items.next(), b_items.next()) {
+                        (Some(a_value), Some(b_value)) => diff_into(a_value, b_value, path, changes),
+                        (Some(a_value), None) => changes.push(BucketChange::Removed { path: path_of(path), value: copy(a_value) }),
+                        (None, Some(b_value)) => changes.push(BucketChange::Added { path: path_of(path), value: copy(b_value) }),
+                        (None, None) => {
+                            path.pop();
+                            break;
+                        }
+                    }
+                    path.pop();
+                    index += 1;
+                }
+            }
+            // such as a unit struct and a unit
+            _ if plain(a.slice, false) == plain(b.slice, false) => {}
+            _ => changes.push(BucketChange::Changed { path: path_of(path), old: copy(a), new: copy(b) }),
+        },
+    }
+}
+
+/// a map key, matching struct fields with string keys of the same name
+#[derive(PartialEq, Eq, Hash)]
+enum EntryKey<'r> {
+    Str(&'r str),
+    Nodes(&'r [BucketNode<'r>]),
+}
+
+impl<'r> EntryKey<'r> {
+    fn of(key: BucketRef<'r>) -> Self {
+        match key.slice.first().and_then(BucketNode::as_str) {
+            Some(key) => EntryKey::Str(key),
+            None => EntryKey::Nodes(key.slice),
+        }
+    }
+
+    fn segment(&self) -> PathSegment {
+        match self {
+            EntryKey::Str(key) => PathSegment::Key((*key).to_owned()),
+            EntryKey::Nodes(nodes) => key_segment(nodes.first()),
+        }
+    }
+}
+
+impl<'r> BucketDiff<'r> {
+    /// the changes, in order
+    pub fn changes(&self) -> &[BucketChange<'r>] {
+        &self.changes
+    }
+
+    /// the number of changes
+    pub fn len(&self) -> usize {
+        self.changes.len()
+    }
+
+    /// if the buckets are the same
+    pub fn is_empty(&self) -> bool {
+        self.changes.is_empty()
+    }
+
+    /// iterate over the changes
+    pub fn iter(&self) -> slice::Iter<'_, BucketChange<'r>> {
+        self.changes.iter()
+    }
+
+    /// convert into a diff that doesn't borrow the buckets, copying any borrowed strings/bytes
+    pub fn into_owned(self) -> BucketDiff<'static> {
+        BucketDiff {
+            changes: self.changes.into_iter().map(BucketChange::into_owned).collect(),
+        }
+    }
+}
+
+impl<'r> IntoIterator for BucketDiff<'r> {
+    type Item = BucketChange<'r>;
+    type IntoIter = vec::IntoIter<BucketChange<'r>>;
+
+    fn into_iter(self) -> Self::IntoIter {
+        self.changes.into_iter()
+    }
+}
+
+impl<'x, 'r> IntoIterator for &'x BucketDiff<'r> {
+    type Item = &'x BucketChange<'r>;
+    type IntoIter = slice::Iter<'x, BucketChange<'r>>;
+
+    fn into_iter(self) -> Self::IntoIter {
+        self.changes.iter()
+    }
+}
+
+impl<'r> BucketChange<'r> {
+    /// the path of the value that changed
+    pub fn path(&self) -> &BucketPath {
+        match self {
+            BucketChange::Added { path, .. } => path,
+            BucketChange::Removed { path, .. } => path,
+            BucketChange::Changed { path, .. } => path,
+        }
+    }
+
+    /// convert into a change that doesn't borrow the buckets, copying any borrowed strings/bytes
+    pub fn into_owned(self) -> BucketChange<'static> {
+        match self {
+            BucketChange::Added { path, value } => BucketChange::Added { path, value: value.into_owned() },
+            BucketChange::Removed { path, value } => BucketChange::Removed { path, value: value.into_owned() },
+            BucketChange::Changed { path, old, new } => BucketChange::Changed { path, old: old.into_owned(), new: new.into_owned() },
+        }
+    }
+}
+
+impl<'r> Serialize for BucketDiff<'r> {
+    fn serialize<S>(&self, serializer: S) -> Result<S::Ok, S::Error> where S: Serializer {
+        serializer.collect_seq(&self.changes)
+    }
+}
+
+/// the path of a change, serialized as a string
+struct PathString<'p>(&'p BucketPath);
+
+impl<'p> Serialize for PathString<'p> {
+    fn serialize<S>(&self, serializer: S) -> Result<S::Ok, S::Error> where S: Serializer {
+        serializer.collect_str(self.0)
+    }
+}
+
+impl<'r> Serialize for BucketChange<'r> {
+    fn serialize<S>(&self, serializer: S) -> Result<S::Ok, S::Error> where S: Serializer {
+        match self {
+            BucketChange::Added { path, value } | BucketChange::Removed { path, value } => {
+                let kind = if matches!(self, BucketChange::Added { .. }) { "added" } else { "removed" };
+                let mut change = serializer.serialize_struct("BucketChange", 3)?;
+                change.serialize_field("kind", kind)?;
+                change.serialize_field("path", &PathString(path))?;
+                change.serialize_field("value", value)?;
+                change.end()
+            }
+            BucketChange::Changed { path, old, new } => {
+                let mut change = serializer.serialize_struct("BucketChange", 4)?;
+                change.serialize_field("kind", "changed")?;
+                change.serialize_field("path", &PathString(path))?;
+                change.serialize_field("old", old)?;
+                change.serialize_field("new", new)?;
+                change.end()
+            }
+        }
+    }
+}
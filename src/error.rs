@@ -1,5 +1,8 @@
 use std::error::Error;
 use std::fmt::{Debug, Display, Formatter};
+use serde::de::{Expected, Unexpected};
+use crate::fault::Fault;
+use crate::path::{BucketPath, PathError};
 
 /// an optional error type for serde
 ///
@@ -22,8 +25,289 @@ impl Display for PseudoError {
     }
 }
 
+impl PseudoError {
+    /// a new error with the given message
+    pub fn new(msg: impl Into<String>) -> Self {
+        Self(msg.into())
+    }
+}
+
 impl serde::de::Error for PseudoError {
     fn custom<T>(msg: T) -> Self where T: Display {
         Self(msg.to_string())
     }
-}
\ No newline at end of file
+}
+
+impl serde::ser::Error for PseudoError {
+    fn custom<T>(msg: T) -> Self where T: Display {
+        Self(msg.to_string())
+    }
+}
+
+/// A structured error for buckets
+///
+/// Implements both `serde::de::Error` and `serde::ser::Error`. When used as the error type for
+/// deserializing a bucket, it also records which node (and, where available, which path) failed.
+///
+/// Node indexes are relative to the nodes being deserialized - so for a segment or view,
+/// they count from the start of that value.
+///
+/// The location is kept alongside the [`BucketErrorKind`] rather than in each kind, so every
+/// kind (including ones made by the type being deserialized, like `MissingField`) has one, and
+/// it can be added to an error as it's returned from the value that failed - match on
+/// [`kind`](BucketError::kind) for the cause.
+#[derive(Clone, Debug, PartialEq)]
+pub struct BucketError {
+    pub(crate) kind: BucketErrorKind,
+    pub(crate) index: Option<usize>,
+    pub(crate) path: Option<BucketPath>,
+}
+
+/// The kind of a [`BucketError`]
+#[derive(Clone, Debug, PartialEq)]
+pub enum BucketErrorKind {
+    /// the value was already taken by `deserialize_into`
+    Consumed,
+    /// a sequence or map was left without it's size (from a capture that failed part-way)
+    MissingSize,
+    /// a value runs past the end of the bucket
+    OutOfBounds,
+    /// the nodes of the bucket aren't well-formed
+    Malformed(&'static str),
+    /// a path couldn't be parsed, or has no value
+    Path(PathError),
+    /// the value has the wrong type - the value found, and what was expected
+    InvalidType(UnexpectedValue, String),
+    /// the value has the right type, but is wrong - the value found, and what was expected
+    InvalidValue(UnexpectedValue, String),
+    /// a sequence or map has the wrong length - the length found, and what was expected
+    InvalidLength(usize, String),
+    /// a map's keys and values were read out of order
+    OutOfOrder(&'static str),
+    /// an enum variant that isn't one of the type's - the variant found, and the variants expected
+    UnknownVariant(String, &'static [&'static str]),
+    /// a struct field that isn't one of the type's - the field found, and the fields expected
+    UnknownField(String, &'static [&'static str]),
+    /// a struct field that wasn't in the map
+    MissingField(&'static str),
+    /// a struct field that was in the map more than once
+    DuplicateField(&'static str),
+    /// any other error
+    Custom(String),
+}
+
+/// An owned `serde::de::Unexpected`, for keeping in a [`BucketError`]
+#[derive(Clone, Debug, PartialEq)]
+pub enum UnexpectedValue {
+    Bool(bool),
+    Unsigned(u64),
+    Signed(i64),
+    Float(f64),
+    Char(char),
+    Str(String),
+    Bytes(Vec<u8>),
+    Unit,
+    Option,
+    NewtypeStruct,
+    Seq,
+    Map,
+    Enum,
+    UnitVariant,
+    NewtypeVariant,
+    TupleVariant,
+    StructVariant,
+    Other(String),
+}
+
+impl UnexpectedValue {
+    /// borrow as a `serde::de::Unexpected`
+    pub fn as_unexpected(&self) -> Unexpected<'_> {
+        match self {
+            UnexpectedValue::Bool(val) => Unexpected::Bool(*val),
+            UnexpectedValue::Unsigned(val) => Unexpected::Unsigned(*val),
+            UnexpectedValue::Signed(val) => Unexpected::Signed(*val),
+            UnexpectedValue::Float(val) => Unexpected::Float(*val),
+            UnexpectedValue::Char(val) => Unexpected::Char(*val),
+            UnexpectedValue::Str(val) => Unexpected::Str(val),
+            UnexpectedValue::Bytes(val) => Unexpected::Bytes(val),
+            UnexpectedValue::Unit => Unexpected::Unit,
+            UnexpectedValue::Option => Unexpected::Option,
+            UnexpectedValue::NewtypeStruct => Unexpected::NewtypeStruct,
+            UnexpectedValue::Seq => Unexpected::Seq,
+            UnexpectedValue::Map => Unexpected::Map,
+            UnexpectedValue::Enum => Unexpected::Enum,
+            UnexpectedValue::UnitVariant => Unexpected::UnitVariant,
+            UnexpectedValue::NewtypeVariant => Unexpected::NewtypeVariant,
+            UnexpectedValue::TupleVariant => Unexpected::TupleVariant,
+            UnexpectedValue::StructVariant => Unexpected::StructVariant,
+            UnexpectedValue::Other(val) => Unexpected::Other(val),
+        }
+    }
+}
+
+impl<'a> From<Unexpected<'a>> for UnexpectedValue {
+    fn from(unexpected: Unexpected<'a>) -> Self {
+        match unexpected {
+            Unexpected::Bool(val) => UnexpectedValue::Bool(val),
+            Unexpected::Unsigned(val) => UnexpectedValue::Unsigned(val),
+            Unexpected::Signed(val) => UnexpectedValue::Signed(val),
+            Unexpected::Float(val) => UnexpectedValue::Float(val),
+            Unexpected::Char(val) => UnexpectedValue::Char(val),
+            Unexpected::Str(val) => UnexpectedValue::Str(val.to_owned()),
+            Unexpected::Bytes(val) => UnexpectedValue::Bytes(val.to_vec()),
+            Unexpected::Unit => UnexpectedValue::Unit,
+            Unexpected::Option => UnexpectedValue::Option,
+            Unexpected::NewtypeStruct => UnexpectedValue::NewtypeStruct,
+            Unexpected::Seq => UnexpectedValue::Seq,
+            Unexpected::Map => UnexpectedValue::Map,
+            Unexpected::Enum => UnexpectedValue::Enum,
+            Unexpected::UnitVariant => UnexpectedValue::UnitVariant,
+            Unexpected::NewtypeVariant => UnexpectedValue::NewtypeVariant,
+            Unexpected::TupleVariant => UnexpectedValue::TupleVariant,
+            Unexpected::StructVariant => UnexpectedValue::StructVariant,
+            Unexpected::Other(val) => UnexpectedValue::Other(val.to_owned()),
+        }
+    }
+}
+
+impl Display for UnexpectedValue {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        Display::fmt(&self.as_unexpected(), f)
+    }
+}
+
+impl BucketError {
+    /// a new error of the given kind, without a location
+    pub fn new(kind: BucketErrorKind) -> Self {
+        Self { kind, index: None, path: None }
+    }
+
+    /// the kind of error
+    pub fn kind(&self) -> &BucketErrorKind {
+        &self.kind
+    }
+
+    /// the index of the node that failed, if known
+    pub fn index(&self) -> Option<usize> {
+        self.index
+    }
+
+    /// the path of the value that failed, if known
+    pub fn path(&self) -> Option<&BucketPath> {
+        self.path.as_ref()
+    }
+
+    pub(crate) fn from_fault(fault: Fault, index: Option<usize>) -> Self {
+        let (kind, path) = match fault {
+            Fault::Consumed => (BucketErrorKind::Consumed, None),
+            Fault::MissingSize => (BucketErrorKind::MissingSize, None),
+            Fault::OutOfBounds => (BucketErrorKind::OutOfBounds, None),
+            Fault::Malformed(problem) => (BucketErrorKind::Malformed(problem), None),
+            Fault::OutOfOrder(problem) => (BucketErrorKind::OutOfOrder(problem), None),
+            Fault::Path(PathError::Missing(path)) => (BucketErrorKind::Path(PathError::Missing(path.clone())), Some(path)),
+            Fault::Path(error) => (BucketErrorKind::Path(error), None),
+        };
+        Self { kind, index, path }
+    }
+
+}
+
+impl From<BucketErrorKind> for BucketError {
+    fn from(kind: BucketErrorKind) -> Self {
+        Self::new(kind)
+    }
+}
+
+impl Error for BucketError {}
+
+impl Display for BucketErrorKind {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        match self {
+            BucketErrorKind::Consumed => Display::fmt(&Fault::Consumed, f),
+            BucketErrorKind::MissingSize => Display::fmt(&Fault::MissingSize, f),
+            BucketErrorKind::OutOfBounds => Display::fmt(&Fault::OutOfBounds, f),
+            BucketErrorKind::Malformed(problem) => Display::fmt(&Fault::Malformed(problem), f),
+            BucketErrorKind::OutOfOrder(problem) => Display::fmt(&Fault::OutOfOrder(problem), f),
+            BucketErrorKind::Path(error) => Display::fmt(error, f),
+            BucketErrorKind::InvalidType(unexpected, expected) => write!(f, "invalid type: {}, expected {}", unexpected, expected),
+            BucketErrorKind::InvalidValue(unexpected, expected) => write!(f, "invalid value: {}, expected {}", unexpected, expected),
+            BucketErrorKind::InvalidLength(len, expected) => write!(f, "invalid length {}, expected {}", len, expected),
+            BucketErrorKind::UnknownVariant(variant, expected) => write!(f, "unknown variant `{}`, {}", variant, OneOf(expected, "variants")),
+            BucketErrorKind::UnknownField(field, expected) => write!(f, "unknown field `{}`, {}", field, OneOf(expected, "fields")),
+            BucketErrorKind::MissingField(field) => write!(f, "missing field `{}`", field),
+            BucketErrorKind::DuplicateField(field) => write!(f, "duplicate field `{}`", field),
+            BucketErrorKind::Custom(msg) => f.write_str(msg),
+        }
+    }
+}
+
+impl Display for BucketError {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        Display::fmt(&self.kind, f)?;
+        match (&self.path, self.index) {
+            (Some(path), _) if !matches!(self.kind, BucketErrorKind::Path(_)) => write!(f, " at {}", path),
+            (_, Some(index)) => write!(f, " at node {}", index),
+            _ => Ok(()),
+        }
+    }
+}
+
+/// formats the expected variants/fields, in the same way as serde
+struct OneOf(&'static [&'static str], &'static str);
+
+impl Display for OneOf {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        match self.0 {
+            [] => write!(f, "there are no {}", self.1),
+            [one] => write!(f, "expected `{}`", one),
+            [first, second] => write!(f, "expected `{}` or `{}`", first, second),
+            [rest @ .., last] => {
+                f.write_str("expected one of ")?;
+                for item in rest {
+                    write!(f, "`{}`, ", item)?;
+                }
+                write!(f, "or `{}`", last)
+            }
+        }
+    }
+}
+
+impl serde::de::Error for BucketError {
+    fn custom<T>(msg: T) -> Self where T: Display {
+        Self::new(BucketErrorKind::Custom(msg.to_string()))
+    }
+
+    fn invalid_type(unexp: Unexpected, exp: &dyn Expected) -> Self {
+        Self::new(BucketErrorKind::InvalidType(unexp.into(), exp.to_string()))
+    }
+
+    fn invalid_value(unexp: Unexpected, exp: &dyn Expected) -> Self {
+        Self::new(BucketErrorKind::InvalidValue(unexp.into(), exp.to_string()))
+    }
+
+    fn invalid_length(len: usize, exp: &dyn Expected) -> Self {
+        Self::new(BucketErrorKind::InvalidLength(len, exp.to_string()))
+    }
+
+    fn unknown_variant(variant: &str, expected: &'static [&'static str]) -> Self {
+        Self::new(BucketErrorKind::UnknownVariant(variant.to_owned(), expected))
+    }
+
+    fn unknown_field(field: &str, expected: &'static [&'static str]) -> Self {
+        Self::new(BucketErrorKind::UnknownField(field.to_owned(), expected))
+    }
+
+    fn missing_field(field: &'static str) -> Self {
+        Self::new(BucketErrorKind::MissingField(field))
+    }
+
+    fn duplicate_field(field: &'static str) -> Self {
+        Self::new(BucketErrorKind::DuplicateField(field))
+    }
+}
+
+impl serde::ser::Error for BucketError {
+    fn custom<T>(msg: T) -> Self where T: Display {
+        Self::new(BucketErrorKind::Custom(msg.to_string()))
+    }
+}
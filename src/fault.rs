@@ -0,0 +1,124 @@
+use std::fmt::{Display, Formatter};
+#[cfg(feature = "error")]
+use std::any::TypeId;
+#[cfg(feature = "error")]
+use std::marker::PhantomData;
+#[cfg(feature = "error")]
+use std::mem::{self, ManuallyDrop};
+#[cfg(feature = "error")]
+use crate::error::BucketError;
+use crate::path::{BucketPath, PathError, PathSegment};
+
+/// a problem with the nodes of a bucket (rather than with the type being deserialized)
+#[derive(Clone, Debug, PartialEq)]
+pub(crate) enum Fault {
+    Consumed,
+    MissingSize,
+    OutOfBounds,
+    Malformed(&'static str),
+    OutOfOrder(&'static str),
+    Path(PathError),
+}
+
+impl Display for Fault {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Fault::Consumed => f.write_str("value has already been consumed"),
+            Fault::MissingSize => f.write_str("invalid value - no size data"),
+            Fault::OutOfBounds => f.write_str("out-of-bounds"),
+            Fault::Malformed(problem) => write!(f, "invalid bucket - {}", problem),
+            Fault::OutOfOrder(problem) => write!(f, "out-of-order access - {}", problem),
+            Fault::Path(error) => Display::fmt(error, f),
+        }
+    }
+}
+
+impl Fault {
+    /// the error for this fault, at the given node (if known)
+    ///
+    /// this is a `BucketError` of the matching kind when `E` is one, otherwise a custom error.
+    pub(crate) fn error<E: serde::de::Error>(self, index: Option<usize>) -> E {
+        #[cfg(feature = "error")]
+        if is_bucket_error::<E>() {
+            return into_error(BucketError::from_fault(self, index));
+        }
+        match index {
+            Some(index) => E::custom(format_args!("{} at node {}", self, index)),
+            None => E::custom(&self),
+        }
+    }
+}
+
+/// the `TypeId` of a type that needn't be `'static` - the same for any of it's lifetimes
+#[cfg(feature = "error")]
+fn type_id<T: ?Sized>() -> TypeId {
+    trait NonStaticAny {
+        fn get_type_id(&self) -> TypeId where Self: 'static;
+    }
+
+    impl<T: ?Sized> NonStaticAny for PhantomData<T> {
+        fn get_type_id(&self) -> TypeId where Self: 'static {
+            TypeId::of::<T>()
+        }
+    }
+
+    let phantom = PhantomData::<T>;
+    // SAFETY: only the lifetime bound of the trait object changes, which `TypeId::of` doesn't depend on
+    let phantom = unsafe { mem::transmute::<&dyn NonStaticAny, &(dyn NonStaticAny + 'static)>(&phantom) };
+    NonStaticAny::get_type_id(phantom)
+}
+
+/// if `E` is `BucketError` - which has no lifetimes, so `E` can't differ from it by one
+#[cfg(feature = "error")]
+fn is_bucket_error<E>() -> bool {
+    type_id::<E>() == TypeId::of::<BucketError>()
+}
+
+/// an error as a `BucketError`, if it is one
+#[cfg(feature = "error")]
+fn as_bucket_error<E>(error: &mut E) -> Option<&mut BucketError> {
+    // SAFETY: `E` is `BucketError`
+    is_bucket_error::<E>().then(|| unsafe { &mut *(error as *mut E).cast::<BucketError>() })
+}
+
+/// a `BucketError` as an `E`, when `E` is `BucketError`
+#[cfg(feature = "error")]
+fn into_error<E>(error: BucketError) -> E {
+    assert!(is_bucket_error::<E>());
+    let error = ManuallyDrop::new(error);
+    // SAFETY: `E` is `BucketError`, and the original is never dropped
+    unsafe { (&*error as *const BucketError).cast::<E>().read() }
+}
+
+/// add the location of the value starting at node `index` to an error from it - if it's a
+/// `BucketError` that doesn't have one yet (it's from a value inside this one, or from elsewhere)
+#[cfg(feature = "error")]
+pub(crate) fn locate<E: serde::de::Error>(mut error: E, index: usize, path: Option<&[PathSegment]>) -> E {
+    if let Some(located) = as_bucket_error(&mut error) {
+        located.index = located.index.or(Some(index));
+        if located.path.is_none() {
+            located.path = path.filter(|path| !path.is_empty()).map(|path| BucketPath { segments: path.to_vec() });
+        }
+    }
+    error
+}
+
+/// add the location of the value starting at node `index` to an error from it (only `BucketError` holds one)
+#[cfg(not(feature = "error"))]
+pub(crate) fn locate<E: serde::de::Error>(error: E, _index: usize, _path: Option<&[PathSegment]>) -> E {
+    error
+}
+
+/// add the path of the value that failed to an error, if it's not the root
+pub(crate) fn attach_path<E: serde::de::Error>(#[allow(unused_mut)] mut error: E, path: &[PathSegment]) -> E {
+    if path.is_empty() {
+        return error;
+    }
+    #[cfg(feature = "error")]
+    if let Some(located) = as_bucket_error(&mut error) {
+        located.path = located.path.take().or_else(|| Some(BucketPath { segments: path.to_vec() }));
+        return error;
+    }
+    let path = BucketPath { segments: path.to_vec() };
+    E::custom(format_args!("{} at {}", error, path))
+}
@@ -1,22 +1,37 @@
-
-
-#[doc = include_str!("../README.md")]
+#![doc = include_str!("../readme.md")]
 
 mod node;
 mod bucket;
 mod debug;
 mod de;
+mod fault;
 
 pub use bucket::*;
 
 #[cfg(feature = "error")]
 mod error;
 mod ser;
-
-// mod segment;
+mod segment;
+mod view;
+mod path;
+mod limits;
+mod diff;
+mod patch;
+mod merge;
+
+pub use segment::*;
+pub use view::*;
+pub use path::*;
+pub use limits::*;
+pub use diff::*;
+pub use patch::*;
+pub use merge::*;
 
 #[cfg(feature = "error")]
 pub use error::*;
 
 #[cfg(feature="deserializer")]
-pub use de::*;
\ No newline at end of file
+pub use de::*;
+
+#[cfg(feature="serializer")]
+pub use ser::*;
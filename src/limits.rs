@@ -0,0 +1,59 @@
+use std::fmt::{Display, Formatter};
+
+/// Limits on the values captured into a `Bucket`
+///
+/// For parsing untrusted input - a capture that goes over a limit fails with a custom error
+/// of the format, before it can use up the stack or memory. Every limit defaults to unlimited.
+/// Set with [`BucketSeed::with_limits`](crate::BucketSeed::with_limits) (or
+/// [`OwnedBucketSeed::with_limits`](crate::OwnedBucketSeed::with_limits)).
+///
+/// ```
+/// use serde::de::DeserializeSeed;
+/// use serde_bucket::{Bucket, BucketLimits};
+///
+/// let limits = BucketLimits { max_depth: 2, ..BucketLimits::default() };
+/// let mut json = serde_json::Deserializer::from_str("[[[1]]]");
+/// let mut bucket = Bucket::with_capacity(0);
+/// assert!(bucket.seed().with_limits(limits).deserialize(&mut json).is_err());
+/// ```
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub struct BucketLimits {
+    /// the deepest nesting of sequences, maps, options, newtypes and enums
+    pub max_depth: usize,
+    /// the most nodes in the bucket (roughly one per value, key and container)
+    pub max_nodes: usize,
+    /// the longest string (or bytes), in bytes
+    pub max_string_len: usize,
+    /// the most bytes of strings (and bytes) in total
+    pub max_total_bytes: usize,
+}
+
+impl Default for BucketLimits {
+    fn default() -> Self {
+        Self {
+            max_depth: usize::MAX,
+            max_nodes: usize::MAX,
+            max_string_len: usize::MAX,
+            max_total_bytes: usize::MAX,
+        }
+    }
+}
+
+/// the limit a capture went over
+pub(crate) enum LimitExceeded {
+    Depth(usize),
+    Nodes(usize),
+    StringLen(usize, usize),
+    TotalBytes(usize),
+}
+
+impl Display for LimitExceeded {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        match self {
+            LimitExceeded::Depth(max) => write!(f, "bucket limit exceeded - nested deeper than {}", max),
+            LimitExceeded::Nodes(max) => write!(f, "bucket limit exceeded - more than {} nodes", max),
+            LimitExceeded::StringLen(len, max) => write!(f, "bucket limit exceeded - string of {} bytes is longer than {}", len, max),
+            LimitExceeded::TotalBytes(max) => write!(f, "bucket limit exceeded - more than {} bytes of strings", max),
+        }
+    }
+}
@@ -0,0 +1,166 @@
+use crate::bucket::Bucket;
+use crate::node::{BucketNode, container, value_len};
+
+/// How [`Bucket::merge_with`] combines two sequences
+#[derive(Copy, Clone, Debug, Default, PartialEq, Eq)]
+pub enum SeqMerge {
+    /// the sequence is replaced, as in JSON Merge Patch
+    #[default]
+    Replace,
+    /// the elements are appended to the sequence
+    Append,
+    /// elements are merged with the element at the same index, and any extra elements are appended
+    ByIndex,
+}
+
+impl<'a> Bucket<'a> {
+    /// merge another bucket into this one, as a [JSON Merge Patch]
+    ///
+    /// Maps are merged deeply - entries of `other` are merged into the entry with the same key,
+    /// and entries with a null value (`None` or unit) remove the key. Any other value
+    /// (including a sequence) replaces the value it's merged into.
+    ///
+    /// Keys are matched by their string, so a captured struct merges with a map holding it's field names.
+    /// The struct keeps it's name while only it's fields are merged - adding any other key makes it a plain map.
+    ///
+    /// [JSON Merge Patch]: https://www.rfc-editor.org/rfc/rfc7386
+    pub fn merge(&mut self, other: &Bucket<'a>) {
+        self.merge_with(other, SeqMerge::Replace);
+    }
+
+    /// merge another bucket into this one, like [`merge`](Bucket::merge) - combining sequences
+    /// with the `seqs` strategy
+    pub fn merge_with(&mut self, other: &Bucket<'a>, seqs: SeqMerge) {
+        if other.inner.is_empty() {
+            return;
+        }
+        let mut nodes = Vec::with_capacity(self.inner.len() + other.inner.len());
+        let target = (!self.inner.is_empty()).then_some(&self.inner[..]);
+        merge_into(target, &other.inner, seqs, &mut nodes);
+        self.inner = nodes;
+    }
+}
+
+fn is_null(value: &[BucketNode]) -> bool {
+    matches!(value.first(), Some(BucketNode::None | BucketNode::Unit))
+}
+
+/// the header nodes (struct name, if any) and the children of a sequence or map, if the value is one
+///
+/// each map entry is two children, it's key and value.
+fn children<'n, 'a>(value: &'n [BucketNode<'a>], seq: bool) -> Option<(&'n [BucketNode<'a>], Vec<&'n [BucketNode<'a>]>)> {
+    let header = container(value);
+    let size = match value.get(header)? {
+        BucketNode::Seq(size, _) if seq => *size,
+        BucketNode::Map(size, _) if !seq => *size * 2,
+        _ => return None,
+    };
+    let mut children = Vec::with_capacity(size);
+    let mut position = header + 1;
+    for _ in 0..size {
+        let len = value_len(value, position)?;
+        children.push(&value[position..position + len]);
+        position += len;
+    }
+    Some((&value[..header], children))
+}
+
+/// if two map keys are equal - comparing string-like keys (including struct fields) by their string
+fn same_key(a: &[BucketNode], b: &[BucketNode]) -> bool {
+    match (a, b) {
+        ([a], [b]) => match (a.as_str(), b.as_str()) {
+            (Some(a), Some(b)) => a == b,
+            _ => a == b,
+        },
+        _ => a == b,
+    }
+}
+
+/// write a map key to `out` - as a string, if it's a field of a struct that's become a plain map
+fn push_key<'a>(key: &[BucketNode<'a>], named: bool, out: &mut Vec<BucketNode<'a>>) {
+    match key {
+        [BucketNode::Field(name)] if !named => out.push(BucketNode::StringRef(name)),
+        _ => out.extend(key.iter().cloned()),
+    }
+}
+
+/// write `patch` merged into `target` (or into nothing) to `out`
+fn merge_into<'a>(target: Option<&[BucketNode<'a>]>, patch: &[BucketNode<'a>], seqs: SeqMerge, out: &mut Vec<BucketNode<'a>>) {
+    if let Some((patch_header, entries)) = children(patch, false) {
+        let target = target.and_then(|target| children(target, false));
+        let mut merged = vec![false; entries.len() / 2];
+        let patched: Vec<Option<usize>> = target.iter()
+            .flat_map(|(_, target_entries)| target_entries.chunks_exact(2))
+            .map(|entry| entries.chunks_exact(2).position(|patch| same_key(patch[0], entry[0])))
+            .collect();
+        for &index in patched.iter().flatten() {
+            merged[index] = true;
+        }
+        let added = entries.chunks_exact(2).zip(&merged).any(|(entry, merged)| !merged && !is_null(entry[1]));
+        // a struct keeps it's name (and field keys) unless keys that aren't it's fields are added to it
+        let named = match &target {
+            Some((header, _)) => !header.is_empty() && !added,
+            None => !patch_header.is_empty(),
+        };
+        let (header, target_entries) = target.unwrap_or((patch_header, Vec::new()));
+        if named {
+            out.extend(header.iter().cloned());
+        }
+        let start = out.len();
+        out.push(BucketNode::Map(0, 0));
+        let mut size = 0;
+        for (entry, patched) in target_entries.chunks_exact(2).zip(patched) {
+            match patched.map(|index| entries[index * 2 + 1]) {
+                Some(value) if is_null(value) => continue,
+                Some(value) => {
+                    push_key(entry[0], named, out);
+                    merge_into(Some(entry[1]), value, seqs, out);
+                }
+                None => {
+                    push_key(entry[0], named, out);
+                    out.extend(entry[1].iter().cloned());
+                }
+            }
+            size += 1;
+        }
+        for (entry, _) in entries.chunks_exact(2).zip(merged).filter(|(_, merged)| !merged) {
+            if !is_null(entry[1]) {
+                push_key(entry[0], named, out);
+                merge_into(None, entry[1], seqs, out);
+                size += 1;
+            }
+        }
+        out[start] = BucketNode::Map(size, out.len() - start - 1);
+        return;
+    }
+    let seq = match (seqs, target.and_then(|target| children(target, true)), children(patch, true)) {
+        (SeqMerge::Replace, ..) | (_, None, _) | (_, _, None) => None,
+        (_, Some(target), Some((_, elements))) => Some((target, elements)),
+    };
+    let Some(((header, target_elements), elements)) = seq else {
+        out.extend(patch.iter().cloned());
+        return;
+    };
+    out.extend(header.iter().cloned());
+    let start = out.len();
+    out.push(BucketNode::Seq(0, 0));
+    let size = match seqs {
+        SeqMerge::ByIndex => {
+            for index in 0..target_elements.len().max(elements.len()) {
+                match (target_elements.get(index), elements.get(index)) {
+                    (target, Some(element)) => merge_into(target.copied(), element, seqs, out),
+                    (Some(target), None) => out.extend(target.iter().cloned()),
+                    (None, None) => unreachable!(),
+                }
+            }
+            target_elements.len().max(elements.len())
+        }
+        _ => {
+            for element in target_elements.iter().chain(&elements) {
+                out.extend(element.iter().cloned());
+            }
+            target_elements.len() + elements.len()
+        }
+    };
+    out[start] = BucketNode::Seq(size, out.len() - start - 1);
+}
@@ -1,6 +1,11 @@
+use std::cmp::Ordering;
+use std::hash::{Hash, Hasher};
+use std::mem;
+use crate::fault::Fault;
+
 
 /// the inner type of a node queue
-#[derive(Default)]
+#[derive(Default, Clone)]
 pub(crate) enum BucketNode<'a> {
     #[default]
     Consumed,
@@ -29,8 +34,525 @@ pub(crate) enum BucketNode<'a> {
     None,
     Some,
 
-    Seq(usize),
-    Map(usize),
+    /// a sequence - the number of elements, and the number of nodes they span
+    Seq(usize, usize),
+    /// a map - the number of entries, and the number of nodes they span
+    Map(usize, usize),
+
+    NewType,
+
+    /// a named unit struct
+    UnitStruct(&'static str),
+    /// a named newtype struct, followed by it's value
+    NewTypeStruct(&'static str),
+    /// a named tuple struct, followed by a `Seq` of it's fields
+    TupleStruct(&'static str),
+    /// a named struct, followed by a `Map` of it's fields (keyed by `Field`)
+    Struct(&'static str),
+    /// a struct (or struct variant) field name
+    Field(&'static str),
 
+    /// an enum variant (enum name, variant index, variant name and kind),
+    /// followed by it's payload - nothing, a value, a `Seq` or a `Map`
+    Variant(&'static str, u32, &'static str, VariantKind),
+    /// an enum from a data format, followed by the variant identifier and it's payload
+    /// (`Unit` for a unit variant)
+    Enum,
+}
+
+/// the payload of an enum variant
+#[derive(Copy, Clone, Eq, PartialEq, Ord, PartialOrd, Hash)]
+pub(crate) enum VariantKind {
+    Unit,
     NewType,
+    Tuple,
+    Struct,
+}
+
+impl<'a> BucketNode<'a> {
+    /// the number of values following this node that belong to it
+    pub(crate) fn children(&self) -> usize {
+        match self {
+            BucketNode::Seq(size, _) => *size,
+            BucketNode::Map(size, _) => size.saturating_mul(2),
+            BucketNode::Some
+            | BucketNode::NewType
+            | BucketNode::NewTypeStruct(_)
+            | BucketNode::TupleStruct(_)
+            | BucketNode::Struct(_) => 1,
+            BucketNode::Variant(_, _, _, VariantKind::Unit) => 0,
+            BucketNode::Variant(..) => 1,
+            BucketNode::Enum => 2,
+            _ => 0,
+        }
+    }
+
+    /// a copy of the node, borrowing (rather than cloning) owned strings and bytes
+    pub(crate) fn borrowed(&self) -> BucketNode<'_> {
+        match self {
+            BucketNode::String(val) => BucketNode::StringRef(val),
+            BucketNode::Bytes(val) => BucketNode::BytesRef(val),
+            BucketNode::StringRef(val) => BucketNode::StringRef(val),
+            BucketNode::BytesRef(val) => BucketNode::BytesRef(val),
+            node => node.clone(),
+        }
+    }
+
+    /// the string value of a string-like node (used for map keys)
+    pub(crate) fn as_str(&self) -> Option<&str> {
+        match self {
+            BucketNode::String(val) => Some(val),
+            BucketNode::StringRef(val) => Some(val),
+            BucketNode::Field(val) => Some(val),
+            _ => None,
+        }
+    }
+}
+
+/// structural equality - owned and borrowed strings (or bytes) are equal, and floats are compared bitwise
+impl PartialEq for BucketNode<'_> {
+    fn eq(&self, other: &Self) -> bool {
+        match (self.borrowed(), other.borrowed()) {
+            (BucketNode::Bool(a), BucketNode::Bool(b)) => a == b,
+            (BucketNode::Char(a), BucketNode::Char(b)) => a == b,
+            (BucketNode::U8(a), BucketNode::U8(b)) => a == b,
+            (BucketNode::I8(a), BucketNode::I8(b)) => a == b,
+            (BucketNode::U16(a), BucketNode::U16(b)) => a == b,
+            (BucketNode::I16(a), BucketNode::I16(b)) => a == b,
+            (BucketNode::U32(a), BucketNode::U32(b)) => a == b,
+            (BucketNode::I32(a), BucketNode::I32(b)) => a == b,
+            (BucketNode::U64(a), BucketNode::U64(b)) => a == b,
+            (BucketNode::I64(a), BucketNode::I64(b)) => a == b,
+            (BucketNode::U128(a), BucketNode::U128(b)) => a == b,
+            (BucketNode::I128(a), BucketNode::I128(b)) => a == b,
+            (BucketNode::F32(a), BucketNode::F32(b)) => a.to_bits() == b.to_bits(),
+            (BucketNode::F64(a), BucketNode::F64(b)) => a.to_bits() == b.to_bits(),
+            (BucketNode::StringRef(a), BucketNode::StringRef(b)) => a == b,
+            (BucketNode::BytesRef(a), BucketNode::BytesRef(b)) => a == b,
+            (BucketNode::Seq(a, a_span), BucketNode::Seq(b, b_span)) => a == b && a_span == b_span,
+            (BucketNode::Map(a, a_span), BucketNode::Map(b, b_span)) => a == b && a_span == b_span,
+            (BucketNode::UnitStruct(a), BucketNode::UnitStruct(b)) => a == b,
+            (BucketNode::NewTypeStruct(a), BucketNode::NewTypeStruct(b)) => a == b,
+            (BucketNode::TupleStruct(a), BucketNode::TupleStruct(b)) => a == b,
+            (BucketNode::Struct(a), BucketNode::Struct(b)) => a == b,
+            (BucketNode::Field(a), BucketNode::Field(b)) => a == b,
+            (BucketNode::Variant(a, a_idx, a_var, a_kind), BucketNode::Variant(b, b_idx, b_var, b_kind)) => {
+                a == b && a_idx == b_idx && a_var == b_var && a_kind == b_kind
+            }
+            // the rest hold no data
+            (a, b) => mem::discriminant(&a) == mem::discriminant(&b) && a.is_marker(),
+        }
+    }
+}
+
+impl Eq for BucketNode<'_> {}
+
+/// hashes agree with equality - owned and borrowed strings (or bytes) hash the same, and floats by their bits
+impl Hash for BucketNode<'_> {
+    fn hash<H: Hasher>(&self, state: &mut H) {
+        let node = self.borrowed();
+        mem::discriminant(&node).hash(state);
+        match node {
+            BucketNode::Bool(val) => val.hash(state),
+            BucketNode::Char(val) => val.hash(state),
+            BucketNode::U8(val) => val.hash(state),
+            BucketNode::I8(val) => val.hash(state),
+            BucketNode::U16(val) => val.hash(state),
+            BucketNode::I16(val) => val.hash(state),
+            BucketNode::U32(val) => val.hash(state),
+            BucketNode::I32(val) => val.hash(state),
+            BucketNode::U64(val) => val.hash(state),
+            BucketNode::I64(val) => val.hash(state),
+            BucketNode::U128(val) => val.hash(state),
+            BucketNode::I128(val) => val.hash(state),
+            BucketNode::F32(val) => val.to_bits().hash(state),
+            BucketNode::F64(val) => val.to_bits().hash(state),
+            BucketNode::StringRef(val) => val.hash(state),
+            BucketNode::BytesRef(val) => val.hash(state),
+            BucketNode::Seq(size, span) | BucketNode::Map(size, span) => (size, span).hash(state),
+            BucketNode::UnitStruct(name)
+            | BucketNode::NewTypeStruct(name)
+            | BucketNode::TupleStruct(name)
+            | BucketNode::Struct(name)
+            | BucketNode::Field(name) => name.hash(state),
+            BucketNode::Variant(name, idx, variant, kind) => (name, idx, variant, kind).hash(state),
+            _ => {}
+        }
+    }
+}
+
+/// a total order agreeing with equality - nodes of different kinds are ordered by kind (as declared),
+/// strings and bytes by their content, and floats by `total_cmp`
+impl Ord for BucketNode<'_> {
+    fn cmp(&self, other: &Self) -> Ordering {
+        let (a, b) = (self.borrowed(), other.borrowed());
+        a.rank().cmp(&b.rank()).then_with(|| match (a, b) {
+            (BucketNode::Bool(a), BucketNode::Bool(b)) => a.cmp(&b),
+            (BucketNode::Char(a), BucketNode::Char(b)) => a.cmp(&b),
+            (BucketNode::U8(a), BucketNode::U8(b)) => a.cmp(&b),
+            (BucketNode::I8(a), BucketNode::I8(b)) => a.cmp(&b),
+            (BucketNode::U16(a), BucketNode::U16(b)) => a.cmp(&b),
+            (BucketNode::I16(a), BucketNode::I16(b)) => a.cmp(&b),
+            (BucketNode::U32(a), BucketNode::U32(b)) => a.cmp(&b),
+            (BucketNode::I32(a), BucketNode::I32(b)) => a.cmp(&b),
+            (BucketNode::U64(a), BucketNode::U64(b)) => a.cmp(&b),
+            (BucketNode::I64(a), BucketNode::I64(b)) => a.cmp(&b),
+            (BucketNode::U128(a), BucketNode::U128(b)) => a.cmp(&b),
+            (BucketNode::I128(a), BucketNode::I128(b)) => a.cmp(&b),
+            (BucketNode::F32(a), BucketNode::F32(b)) => a.total_cmp(&b),
+            (BucketNode::F64(a), BucketNode::F64(b)) => a.total_cmp(&b),
+            (BucketNode::StringRef(a), BucketNode::StringRef(b)) => a.cmp(b),
+            (BucketNode::BytesRef(a), BucketNode::BytesRef(b)) => a.cmp(b),
+            (BucketNode::Seq(a, a_span), BucketNode::Seq(b, b_span)) => (a, a_span).cmp(&(b, b_span)),
+            (BucketNode::Map(a, a_span), BucketNode::Map(b, b_span)) => (a, a_span).cmp(&(b, b_span)),
+            (BucketNode::UnitStruct(a), BucketNode::UnitStruct(b))
+            | (BucketNode::NewTypeStruct(a), BucketNode::NewTypeStruct(b))
+            | (BucketNode::TupleStruct(a), BucketNode::TupleStruct(b))
+            | (BucketNode::Struct(a), BucketNode::Struct(b))
+            | (BucketNode::Field(a), BucketNode::Field(b)) => a.cmp(b),
+            (BucketNode::Variant(a, a_idx, a_var, a_kind), BucketNode::Variant(b, b_idx, b_var, b_kind)) => {
+                (a, a_idx, a_var, a_kind).cmp(&(b, b_idx, b_var, b_kind))
+            }
+            _ => Ordering::Equal,
+        })
+    }
+}
+
+impl PartialOrd for BucketNode<'_> {
+    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+/// a number, without it's width
+#[derive(PartialEq)]
+enum Number {
+    Unsigned(u128),
+    Negative(i128),
+    Float(f64),
+}
+
+impl Number {
+    /// if the numbers have the same value - an integer is equal to a float holding exactly it
+    fn eq_value(&self, other: &Number) -> bool {
+        match (self, other) {
+            (Number::Float(float), Number::Unsigned(int)) | (Number::Unsigned(int), Number::Float(float)) => {
+                *float >= 0.0 && float.fract() == 0.0 && *float < 2f64.powi(128) && *float as u128 == *int
+            }
+            (Number::Float(float), Number::Negative(int)) | (Number::Negative(int), Number::Float(float)) => {
+                *float < 0.0 && float.fract() == 0.0 && *float >= -(2f64.powi(127)) && *float as i128 == *int
+            }
+            _ => self == other,
+        }
+    }
+}
+
+impl<'a> BucketNode<'a> {
+    /// if the node holds no data (other than it's kind)
+    fn is_marker(&self) -> bool {
+        matches!(self,
+            BucketNode::Consumed | BucketNode::Unsized | BucketNode::Unit
+            | BucketNode::None | BucketNode::Some | BucketNode::NewType | BucketNode::Enum
+        )
+    }
+
+    /// the position of the node's kind, for ordering (owned and borrowed kinds are the same)
+    fn rank(&self) -> u8 {
+        match self {
+            BucketNode::Consumed => 0,
+            BucketNode::Unsized => 1,
+            BucketNode::Unit => 2,
+            BucketNode::Bool(_) => 3,
+            BucketNode::Char(_) => 4,
+            BucketNode::U8(_) => 5,
+            BucketNode::I8(_) => 6,
+            BucketNode::U16(_) => 7,
+            BucketNode::I16(_) => 8,
+            BucketNode::U32(_) => 9,
+            BucketNode::I32(_) => 10,
+            BucketNode::U64(_) => 11,
+            BucketNode::I64(_) => 12,
+            BucketNode::U128(_) => 13,
+            BucketNode::I128(_) => 14,
+            BucketNode::F32(_) => 15,
+            BucketNode::F64(_) => 16,
+            BucketNode::String(_) | BucketNode::StringRef(_) => 17,
+            BucketNode::Bytes(_) | BucketNode::BytesRef(_) => 18,
+            BucketNode::None => 19,
+            BucketNode::Some => 20,
+            BucketNode::Seq(..) => 21,
+            BucketNode::Map(..) => 22,
+            BucketNode::NewType => 23,
+            BucketNode::UnitStruct(_) => 24,
+            BucketNode::NewTypeStruct(_) => 25,
+            BucketNode::TupleStruct(_) => 26,
+            BucketNode::Struct(_) => 27,
+            BucketNode::Field(_) => 28,
+            BucketNode::Variant(..) => 29,
+            BucketNode::Enum => 30,
+        }
+    }
+
+    fn number(&self) -> Option<Number> {
+        let signed = |val: i128| if val < 0 { Number::Negative(val) } else { Number::Unsigned(val as u128) };
+        Some(match *self {
+            BucketNode::U8(val) => Number::Unsigned(val as u128),
+            BucketNode::U16(val) => Number::Unsigned(val as u128),
+            BucketNode::U32(val) => Number::Unsigned(val as u128),
+            BucketNode::U64(val) => Number::Unsigned(val as u128),
+            BucketNode::U128(val) => Number::Unsigned(val),
+            BucketNode::I8(val) => signed(val as i128),
+            BucketNode::I16(val) => signed(val as i128),
+            BucketNode::I32(val) => signed(val as i128),
+            BucketNode::I64(val) => signed(val as i128),
+            BucketNode::I128(val) => signed(val),
+            BucketNode::F32(val) => Number::Float(val as f64),
+            BucketNode::F64(val) => Number::Float(val),
+            _ => return None,
+        })
+    }
+
+    /// equality that ignores the width of numbers
+    pub(crate) fn eq_numeric(&self, other: &Self) -> bool {
+        match (self.number(), other.number()) {
+            (Some(a), Some(b)) => a == b,
+            _ => self == other,
+        }
+    }
+}
+
+/// equality of nodes, ignoring the width of numbers
+pub(crate) fn eq_numeric(a: &[BucketNode], b: &[BucketNode]) -> bool {
+    a.len() == b.len() && a.iter().zip(b).all(|(a, b)| a.eq_numeric(b))
+}
+
+/// equality of nodes, comparing numbers by their value - so integers are also equal to floats
+pub(crate) fn eq_value(a: &[BucketNode], b: &[BucketNode]) -> bool {
+    a.len() == b.len() && a.iter().zip(b).all(|(a, b)| match (a.number(), b.number()) {
+        (Some(a), Some(b)) => a.eq_value(&b),
+        _ => a == b,
+    })
+}
+
+/// sort the entries of every map by their key (then value), so maps with the same entries have the same nodes
+///
+/// maps are sorted from the last to the first, so nested maps are sorted before the entries holding them are moved.
+/// maps that aren't well-formed are left as they are.
+pub(crate) fn canonicalize(nodes: &mut [BucketNode]) {
+    for index in (0..nodes.len()).rev() {
+        let BucketNode::Map(size, span) = nodes[index] else {
+            continue;
+        };
+        let Some(entries) = span.checked_add(index + 1).and_then(|end| nodes.get_mut(index + 1..end)) else {
+            continue;
+        };
+        // the (key, value) ranges of each entry
+        let mut ranges = Vec::with_capacity(size);
+        let mut start = 0;
+        for _ in 0..size {
+            let Some(key) = value_len(entries, start) else { break };
+            let Some(value) = value_len(entries, start + key) else { break };
+            ranges.push((start..start + key, start + key..start + key + value));
+            start += key + value;
+        }
+        if ranges.len() != size || start != entries.len() {
+            continue;
+        }
+        ranges.sort_by(|(a_key, a_value), (b_key, b_value)| {
+            entries[a_key.clone()].cmp(&entries[b_key.clone()])
+                .then_with(|| entries[a_value.clone()].cmp(&entries[b_value.clone()]))
+        });
+        let sorted: Vec<BucketNode> = ranges.into_iter()
+            .flat_map(|(key, value)| key.start..value.end)
+            .map(|index| mem::take(&mut entries[index]))
+            .collect();
+        for (entry, node) in entries.iter_mut().zip(sorted) {
+            *entry = node;
+        }
+    }
+}
+
+/// equality of nodes, ignoring the order of map entries (and the names of structs)
+pub(crate) fn eq_unordered(a: &[BucketNode], b: &[BucketNode]) -> bool {
+    let mut a = plain(a, false);
+    let mut b = plain(b, false);
+    if a.len() != b.len() {
+        return false;
+    }
+    canonicalize(&mut a);
+    canonicalize(&mut b);
+    a == b
+}
+
+/// a copy of the nodes (borrowing strings and bytes) as the plain data they serialize as -
+/// structs as their map, with string keys, and without the names of any structs.
+///
+/// With `json`, also as JSON would hold them - without `Some` and newtype wrappers, and `None` as `Unit`.
+pub(crate) fn plain<'r>(nodes: &'r [BucketNode], json: bool) -> Vec<BucketNode<'r>> {
+    let mut plain = Vec::with_capacity(nodes.len());
+    // the containers not yet closed - their position in `plain`, and where they end in `nodes`
+    let mut open: Vec<(usize, usize)> = Vec::new();
+    for (index, node) in nodes.iter().enumerate() {
+        close_plain(&mut plain, &mut open, index);
+        match node {
+            BucketNode::Struct(_) | BucketNode::TupleStruct(_) | BucketNode::NewTypeStruct(_) => {}
+            BucketNode::Some | BucketNode::NewType if json => {}
+            BucketNode::None if json => plain.push(BucketNode::Unit),
+            BucketNode::UnitStruct(_) => plain.push(BucketNode::Unit),
+            BucketNode::Field(name) => plain.push(BucketNode::StringRef(name)),
+            BucketNode::Seq(_, span) | BucketNode::Map(_, span) => {
+                open.push((plain.len(), index.saturating_add(*span).saturating_add(1)));
+                plain.push(node.borrowed());
+            }
+            node => plain.push(node.borrowed()),
+        }
+    }
+    close_plain(&mut plain, &mut open, usize::MAX);
+    plain
+}
+
+/// set the span of the containers that end by `index`, now that their struct names are dropped
+fn close_plain(plain: &mut [BucketNode], open: &mut Vec<(usize, usize)>, index: usize) {
+    while let Some(&(position, end)) = open.last() {
+        if end > index {
+            break;
+        }
+        open.pop();
+        let len = plain.len() - position - 1;
+        if let BucketNode::Seq(_, span) | BucketNode::Map(_, span) = &mut plain[position] {
+            *span = len;
+        }
+    }
+}
+
+/// the offset of the `Seq`/`Map` node of a value, skipping tuple struct/struct names
+pub(crate) fn container(slice: &[BucketNode]) -> usize {
+    match slice.first() {
+        Some(BucketNode::TupleStruct(_) | BucketNode::Struct(_)) => 1,
+        _ => 0,
+    }
+}
+
+/// the number of nodes making up the value at `index` - the node and all of it's children.
+///
+/// sequences and maps are skipped over using their span, so this only walks wrapper nodes.
+/// returns `None` if the value runs past the end of the nodes.
+pub(crate) fn value_len(nodes: &[BucketNode], index: usize) -> Option<usize> {
+    let mut cursor = index;
+    let mut pending = 1usize;
+    while pending > 0 {
+        pending -= 1;
+        match nodes.get(cursor)? {
+            BucketNode::Seq(_, span) | BucketNode::Map(_, span) => cursor = cursor.checked_add(*span)?.checked_add(1)?,
+            node => {
+                pending += node.children();
+                cursor += 1;
+            }
+        }
+    }
+    if cursor > nodes.len() {
+        return None;
+    }
+    Some(cursor - index)
+}
+
+impl<'a> BucketNode<'a> {
+    /// convert into an owned node, copying borrowed strings and bytes
+    pub(crate) fn into_owned(self) -> BucketNode<'static> {
+        match self {
+            BucketNode::Consumed => BucketNode::Consumed,
+            BucketNode::Unsized => BucketNode::Unsized,
+            BucketNode::Unit => BucketNode::Unit,
+            BucketNode::Bool(val) => BucketNode::Bool(val),
+            BucketNode::Char(val) => BucketNode::Char(val),
+            BucketNode::U8(val) => BucketNode::U8(val),
+            BucketNode::I8(val) => BucketNode::I8(val),
+            BucketNode::U16(val) => BucketNode::U16(val),
+            BucketNode::I16(val) => BucketNode::I16(val),
+            BucketNode::U32(val) => BucketNode::U32(val),
+            BucketNode::I32(val) => BucketNode::I32(val),
+            BucketNode::U64(val) => BucketNode::U64(val),
+            BucketNode::I64(val) => BucketNode::I64(val),
+            BucketNode::U128(val) => BucketNode::U128(val),
+            BucketNode::I128(val) => BucketNode::I128(val),
+            BucketNode::F32(val) => BucketNode::F32(val),
+            BucketNode::F64(val) => BucketNode::F64(val),
+            BucketNode::String(val) => BucketNode::String(val),
+            BucketNode::StringRef(val) => BucketNode::String(val.to_owned()),
+            BucketNode::Bytes(val) => BucketNode::Bytes(val),
+            BucketNode::BytesRef(val) => BucketNode::Bytes(val.to_vec()),
+            BucketNode::None => BucketNode::None,
+            BucketNode::Some => BucketNode::Some,
+            BucketNode::Seq(size, span) => BucketNode::Seq(size, span),
+            BucketNode::Map(size, span) => BucketNode::Map(size, span),
+            BucketNode::NewType => BucketNode::NewType,
+            BucketNode::UnitStruct(name) => BucketNode::UnitStruct(name),
+            BucketNode::NewTypeStruct(name) => BucketNode::NewTypeStruct(name),
+            BucketNode::TupleStruct(name) => BucketNode::TupleStruct(name),
+            BucketNode::Struct(name) => BucketNode::Struct(name),
+            BucketNode::Field(name) => BucketNode::Field(name),
+            BucketNode::Variant(name, index, variant, kind) => BucketNode::Variant(name, index, variant, kind),
+            BucketNode::Enum => BucketNode::Enum,
+        }
+    }
+}
+
+/// a container being checked by `validate` - it's remaining values, and where they must end
+struct Frame {
+    pending: usize,
+    end: Option<usize>,
+    limit: usize,
+}
+
+/// check that the nodes hold exactly one well-formed value
+///
+/// every container's length must match it's span, no node may run past the end of it's container,
+/// names must be followed by their fields, and no `Unsized` markers may be left over.
+/// returns the index of the offending node and the problem on failure.
+pub(crate) fn validate(nodes: &[BucketNode]) -> Result<(), (usize, Fault)> {
+    let mut stack = vec![Frame { pending: 1, end: None, limit: nodes.len() }];
+    let mut cursor = 0;
+    while let Some(frame) = stack.last_mut() {
+        if frame.pending == 0 {
+            if frame.end.is_some_and(|end| end != cursor) {
+                return Err((cursor, Fault::Malformed("container length doesn't match it's span")));
+            }
+            stack.pop();
+            continue;
+        }
+        frame.pending -= 1;
+        let limit = frame.limit;
+        let Some(node) = nodes.get(cursor).filter(|_| cursor < limit) else {
+            return Err((cursor, Fault::OutOfBounds));
+        };
+        let next = nodes.get(cursor + 1);
+        match node {
+            BucketNode::Unsized => return Err((cursor, Fault::MissingSize)),
+            BucketNode::Seq(size, span) | BucketNode::Map(size, span) => {
+                let end = match cursor.checked_add(1).and_then(|start| start.checked_add(*span)) {
+                    Some(end) if end <= limit => end,
+                    _ => return Err((cursor, Fault::Malformed("container runs past it's parent"))),
+                };
+                let pending = match node {
+                    BucketNode::Map(..) => size.checked_mul(2).ok_or((cursor, Fault::Malformed("map length overflows")))?,
+                    _ => *size,
+                };
+                if pending > *span {
+                    return Err((cursor, Fault::Malformed("container length doesn't match it's span")));
+                }
+                stack.push(Frame { pending, end: Some(end), limit: end });
+            }
+            BucketNode::TupleStruct(_) | BucketNode::Variant(_, _, _, VariantKind::Tuple)
+                if !matches!(next, Some(BucketNode::Seq(..))) => return Err((cursor, Fault::Malformed("expected tuple fields"))),
+            BucketNode::Struct(_) | BucketNode::Variant(_, _, _, VariantKind::Struct)
+                if !matches!(next, Some(BucketNode::Map(..))) => return Err((cursor, Fault::Malformed("expected struct fields"))),
+            node if node.children() > 0 => stack.push(Frame { pending: node.children(), end: None, limit }),
+            _ => {}
+        }
+        cursor += 1;
+    }
+    if cursor != nodes.len() {
+        return Err((cursor, Fault::Malformed("trailing nodes after the value")));
+    }
+    Ok(())
 }
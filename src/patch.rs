@@ -0,0 +1,494 @@
+use std::error::Error;
+use std::fmt::{Display, Formatter};
+use serde::{Deserialize, Deserializer, Serialize, Serializer};
+use serde::ser::SerializeMap;
+use crate::bucket::Bucket;
+use crate::diff::{BucketChange, diff};
+use crate::node::{BucketNode, canonicalize, eq_value, plain, value_len};
+use crate::path::{BucketPath, PathError, PathSegment, key_segment, parse_index};
+
+/// A [JSON Patch] document - a list of operations to apply to a `Bucket`
+///
+/// Patches can be deserialized from (or serialized as) the JSON Patch format, read from a
+/// bucket with [`from_bucket`](BucketPatch::from_bucket), generated with [`Bucket::patch_to`],
+/// or built from a `Vec` of operations.
+///
+/// [JSON Patch]: https://www.rfc-editor.org/rfc/rfc6902
+#[derive(Debug, Default, PartialEq, Eq)]
+pub struct BucketPatch<'a> {
+    pub(crate) ops: Vec<PatchOp<'a>>,
+}
+
+/// A single operation of a [`BucketPatch`]
+#[derive(Debug, PartialEq, Eq)]
+pub enum PatchOp<'a> {
+    /// add a value - inserting into a sequence (`-` appends), or adding (or replacing) a map entry
+    Add { path: BucketPath, value: Bucket<'a> },
+    /// remove a sequence element or map entry
+    Remove { path: BucketPath },
+    /// replace an existing value
+    Replace { path: BucketPath, value: Bucket<'a> },
+    /// remove a value, and add it at another path
+    Move { from: BucketPath, path: BucketPath },
+    /// add a copy of a value at another path
+    Copy { from: BucketPath, path: BucketPath },
+    /// check a value is equal as JSON - comparing numbers by value (so `1` equals `1.0`), and ignoring
+    /// the order of map entries, the names of structs and `Some` wrappers
+    Test { path: BucketPath, value: Bucket<'a> },
+}
+
+/// An error from reading or applying a [`BucketPatch`]
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub enum PatchError {
+    /// the patch isn't a valid JSON Patch document
+    Invalid(String),
+    /// an operation's path (or `from`) has no value - the index of the operation, and the path
+    Path(usize, PathError),
+    /// an operation can't be applied - the index of the operation, and why
+    Operation(usize, &'static str),
+    /// a `test` operation found a different value - the index of the operation, and the path
+    Test(usize, BucketPath),
+}
+
+impl Error for PatchError {}
+
+impl Display for PatchError {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        match self {
+            PatchError::Invalid(problem) => write!(f, "invalid patch - {}", problem),
+            PatchError::Path(op, error) => write!(f, "patch operation {} failed - {}", op, error),
+            PatchError::Operation(op, problem) => write!(f, "patch operation {} failed - {}", op, problem),
+            PatchError::Test(op, path) => write!(f, "patch operation {} failed - test of `{}` didn't match", op, path),
+        }
+    }
+}
+
+impl<'a> BucketPatch<'a> {
+    /// an empty patch
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// add an operation to the end of the patch
+    pub fn push(&mut self, op: PatchOp<'a>) {
+        self.ops.push(op);
+    }
+
+    /// the operations of the patch, in order
+    pub fn ops(&self) -> &[PatchOp<'a>] {
+        &self.ops
+    }
+
+    /// the number of operations
+    pub fn len(&self) -> usize {
+        self.ops.len()
+    }
+
+    /// if the patch has no operations
+    pub fn is_empty(&self) -> bool {
+        self.ops.is_empty()
+    }
+
+    /// read a patch from a bucket holding a JSON Patch document, copying it's values
+    pub fn from_bucket(document: &Bucket<'_>) -> Result<BucketPatch<'static>, PatchError> {
+        let ops = document.view().as_seq()
+            .ok_or_else(|| PatchError::Invalid("expected a sequence of operations".to_owned()))?;
+        let mut patch = BucketPatch::new();
+        for (index, op) in ops.iter().enumerate() {
+            let invalid = |problem: &str| PatchError::Invalid(format!("operation {} {}", index, problem));
+            let members = op.as_map().ok_or_else(|| invalid("isn't a map"))?;
+            let member = |name: &str| members.get(name);
+            let pointer = |name: &str| match member(name) {
+                Some(value) => value.as_str()
+                    .ok_or_else(|| invalid(&format!("has a `{}` that isn't a string", name)))
+                    .and_then(|pointer| BucketPath::from_pointer(pointer).map_err(|error| PatchError::Path(index, error))),
+                None => Err(invalid(&format!("has no `{}`", name))),
+            };
+            let value = || match member("value") {
+                Some(value) => Ok(Bucket::new(value.slice.iter().cloned().map(BucketNode::into_owned).collect())),
+                None => Err(invalid("has no `value`")),
+            };
+            let path = pointer("path")?;
+            patch.push(match member("op").and_then(|op| op.as_str()) {
+                Some("add") => PatchOp::Add { path, value: value()? },
+                Some("remove") => PatchOp::Remove { path },
+                Some("replace") => PatchOp::Replace { path, value: value()? },
+                Some("move") => PatchOp::Move { from: pointer("from")?, path },
+                Some("copy") => PatchOp::Copy { from: pointer("from")?, path },
+                Some("test") => PatchOp::Test { path, value: value()? },
+                Some(op) => return Err(invalid(&format!("has an unknown op `{}`", op))),
+                None => return Err(invalid("has no `op`")),
+            });
+        }
+        Ok(patch)
+    }
+}
+
+impl<'a> From<Vec<PatchOp<'a>>> for BucketPatch<'a> {
+    fn from(ops: Vec<PatchOp<'a>>) -> Self {
+        Self { ops }
+    }
+}
+
+impl<'de, 'a> Deserialize<'de> for BucketPatch<'a> {
+    fn deserialize<D>(deserializer: D) -> Result<Self, D::Error> where D: Deserializer<'de> {
+        let document = Bucket::deserialize(deserializer)?;
+        BucketPatch::from_bucket(&document).map_err(serde::de::Error::custom)
+    }
+}
+
+impl<'a> Serialize for BucketPatch<'a> {
+    fn serialize<S>(&self, serializer: S) -> Result<S::Ok, S::Error> where S: Serializer {
+        serializer.collect_seq(&self.ops)
+    }
+}
+
+impl<'a> Serialize for PatchOp<'a> {
+    fn serialize<S>(&self, serializer: S) -> Result<S::Ok, S::Error> where S: Serializer {
+        let (op, from, path, value) = match self {
+            PatchOp::Add { path, value } => ("add", None, path, Some(value)),
+            PatchOp::Remove { path } => ("remove", None, path, None),
+            PatchOp::Replace { path, value } => ("replace", None, path, Some(value)),
+            PatchOp::Move { from, path } => ("move", Some(from), path, None),
+            PatchOp::Copy { from, path } => ("copy", Some(from), path, None),
+            PatchOp::Test { path, value } => ("test", None, path, Some(value)),
+        };
+        let mut map = serializer.serialize_map(None)?;
+        map.serialize_entry("op", op)?;
+        if let Some(from) = from {
+            map.serialize_entry("from", &from.to_pointer())?;
+        }
+        map.serialize_entry("path", &path.to_pointer())?;
+        if let Some(value) = value {
+            map.serialize_entry("value", value)?;
+        }
+        map.end()
+    }
+}
+
+impl<'a> Bucket<'a> {
+    /// apply a JSON Patch to the bucket
+    ///
+    /// The operations are applied in order, to a copy of the nodes - so if any of them fail,
+    /// the bucket is left unchanged.
+    pub fn apply_patch(&mut self, patch: &BucketPatch<'a>) -> Result<(), PatchError> {
+        let mut nodes = self.inner.clone();
+        for (index, op) in patch.ops.iter().enumerate() {
+            apply(&mut nodes, op).map_err(|error| error.at(index))?;
+        }
+        self.inner = nodes;
+        Ok(())
+    }
+
+    /// generate a patch that turns this bucket into `other` (see [`diff`](crate::diff))
+    ///
+    /// Values are added, removed or replaced where they differ. Moves aren't detected, so the
+    /// patch isn't always the smallest one - a renamed key is removed and added with it's whole
+    /// value, and an element inserted into a sequence replaces every element after it.
+    pub fn patch_to<'r>(&'r self, other: &'r Bucket<'_>) -> BucketPatch<'r> {
+        let mut patch = BucketPatch::new();
+        let mut removed = Vec::new();
+        for change in diff(self, other) {
+            // sequences lose elements from the end, which are removed last-first to keep their indexes
+            let is_element = matches!(change.path().segments.last(), Some(PathSegment::Index(_)));
+            if !matches!(change, BucketChange::Removed { .. }) || !is_element || !same_parent(&removed, change.path()) {
+                patch.ops.extend(removed.drain(..).rev());
+            }
+            match change {
+                BucketChange::Added { path, value } => patch.push(PatchOp::Add { path, value }),
+                BucketChange::Removed { path, .. } if is_element => removed.push(PatchOp::Remove { path }),
+                BucketChange::Removed { path, .. } => patch.push(PatchOp::Remove { path }),
+                BucketChange::Changed { path, new, .. } => {
+                    // the diff looks through wrappers that both values have, so `new` can be without them
+                    let value = slot(other, &path).unwrap_or(new);
+                    patch.push(PatchOp::Replace { path, value });
+                }
+            }
+        }
+        patch.ops.extend(removed.into_iter().rev());
+        patch
+    }
+}
+
+/// the whole value at a path of a bucket - with any `Some` and newtype wrappers
+fn slot<'r>(bucket: &'r Bucket<'_>, path: &BucketPath) -> Option<Bucket<'r>> {
+    let target = resolve(&bucket.inner, &path.segments).ok()?;
+    let mut value = Bucket::new(bucket.inner[target.index..target.end].iter().map(BucketNode::borrowed).collect());
+    value.config = bucket.config;
+    Some(value)
+}
+
+/// if `path` has the same parent as the (removed) elements
+fn same_parent(removed: &[PatchOp], path: &BucketPath) -> bool {
+    match removed.last() {
+        Some(PatchOp::Remove { path: last }) => last.segments[..last.segments.len() - 1] == path.segments[..path.segments.len() - 1],
+        _ => true,
+    }
+}
+
+/// a failed operation, before it's index is known
+enum Failure {
+    Missing(BucketPath),
+    Operation(&'static str),
+    Test(BucketPath),
+}
+
+impl Failure {
+    fn at(self, index: usize) -> PatchError {
+        match self {
+            Failure::Missing(path) => PatchError::Path(index, PathError::Missing(path)),
+            Failure::Operation(problem) => PatchError::Operation(index, problem),
+            Failure::Test(path) => PatchError::Test(index, path),
+        }
+    }
+}
+
+fn apply<'a>(nodes: &mut Vec<BucketNode<'a>>, op: &PatchOp<'a>) -> Result<(), Failure> {
+    match op {
+        PatchOp::Add { path, value } => add(nodes, path, value.inner.clone()),
+        PatchOp::Remove { path } => remove(nodes, path).map(drop),
+        PatchOp::Replace { path, value } => replace(nodes, path, value.inner.clone()),
+        PatchOp::Move { from, path } => {
+            if from == path {
+                return Ok(());
+            }
+            if path.segments.starts_with(&from.segments) {
+                return Err(Failure::Operation("can't move a value into itself"));
+            }
+            let value = remove(nodes, from)?;
+            add(nodes, path, value)
+        }
+        PatchOp::Copy { from, path } => {
+            let target = resolve(nodes, &from.segments).map_err(|len| missing(from, len))?;
+            let value = nodes[target.index..target.end].to_vec();
+            add(nodes, path, value)
+        }
+        PatchOp::Test { path, value } => {
+            let target = resolve(nodes, &path.segments).map_err(|len| missing(path, len))?;
+            if json_eq(&nodes[target.index..target.end], &value.inner) {
+                Ok(())
+            } else {
+                Err(Failure::Test(path.clone()))
+            }
+        }
+    }
+}
+
+/// the path, up to and including the segment with no value
+fn missing(path: &BucketPath, len: usize) -> Failure {
+    Failure::Missing(BucketPath { segments: path.segments[..(len + 1).min(path.segments.len())].to_vec() })
+}
+
+/// the location of a value in the nodes
+struct Target {
+    /// the `Seq`/`Map` nodes holding the value, outermost first
+    ancestors: Vec<usize>,
+    index: usize,
+    end: usize,
+}
+
+/// find the value at a path, or the number of segments that were found
+fn resolve(nodes: &[BucketNode], path: &[PathSegment]) -> Result<Target, usize> {
+    let mut ancestors = Vec::new();
+    let mut index = 0;
+    for (len, segment) in path.iter().enumerate() {
+        let container = container_at(nodes, index).ok_or(len)?;
+        let (_, value) = find_child(nodes, container, segment).ok_or(len)?;
+        ancestors.push(container);
+        index = value;
+    }
+    let end = index + value_len(nodes, index).ok_or(path.len().saturating_sub(1))?;
+    Ok(Target { ancestors, index, end })
+}
+
+/// the `Seq`/`Map` node of the value at `index`, looking through wrappers and struct names
+fn container_at(nodes: &[BucketNode], mut index: usize) -> Option<usize> {
+    loop {
+        match nodes.get(index)? {
+            BucketNode::Some
+            | BucketNode::NewType
+            | BucketNode::NewTypeStruct(_)
+            | BucketNode::TupleStruct(_)
+            | BucketNode::Struct(_) => index += 1,
+            BucketNode::Seq(..) | BucketNode::Map(..) => return Some(index),
+            _ => return None,
+        }
+    }
+}
+
+/// the (key, value) nodes of the child of a container - for sequences, both are the element
+fn find_child(nodes: &[BucketNode], container: usize, segment: &PathSegment) -> Option<(usize, usize)> {
+    match nodes[container] {
+        BucketNode::Seq(size, _) => {
+            let index = match segment {
+                PathSegment::Index(index) => *index,
+                PathSegment::Key(key) => parse_index(key)?,
+            };
+            if index >= size {
+                return None;
+            }
+            let mut position = container + 1;
+            for _ in 0..index {
+                position += value_len(nodes, position)?;
+            }
+            Some((position, position))
+        }
+        BucketNode::Map(size, _) => {
+            let mut position = container + 1;
+            for _ in 0..size {
+                let value = position + value_len(nodes, position)?;
+                if key_matches(nodes.get(position)?, segment) {
+                    return Some((position, value));
+                }
+                position = value + value_len(nodes, value)?;
+            }
+            None
+        }
+        _ => None,
+    }
+}
+
+/// if a map key matches a path segment - by string, or an integer key by index
+fn key_matches(key: &BucketNode, segment: &PathSegment) -> bool {
+    match segment {
+        PathSegment::Key(name) if key.as_str() == Some(name) => true,
+        PathSegment::Key(name) => parse_index(name).is_some_and(|index| key_segment(Some(key)) == PathSegment::Index(index)),
+        PathSegment::Index(index) => key_segment(Some(key)) == PathSegment::Index(*index),
+    }
+}
+
+/// change the span of every container holding a value, after nodes are inserted or removed
+fn resize(nodes: &mut [BucketNode], ancestors: &[usize], added: usize, removed: usize) -> Result<(), Failure> {
+    for &ancestor in ancestors {
+        if let BucketNode::Seq(_, span) | BucketNode::Map(_, span) = &mut nodes[ancestor] {
+            *span = span.checked_add(added)
+                .and_then(|span| span.checked_sub(removed))
+                .ok_or(Failure::Operation("a container's span doesn't match it's elements"))?;
+        }
+    }
+    Ok(())
+}
+
+/// turn the struct holding the map `container` into a plain map (with string keys),
+/// so it can hold keys that aren't one of it's fields
+fn unname(nodes: &mut Vec<BucketNode>, ancestors: &mut [usize]) -> Result<(), Failure> {
+    let Some((container, outer)) = ancestors.split_last_mut() else {
+        return Ok(());
+    };
+    if *container == 0 || !matches!(nodes[*container - 1], BucketNode::Struct(_)) {
+        return Ok(());
+    }
+    resize(nodes, outer, 0, 1)?;
+    nodes.remove(*container - 1);
+    *container -= 1;
+    let BucketNode::Map(size, _) = nodes[*container] else {
+        return Ok(());
+    };
+    let mut position = *container + 1;
+    for _ in 0..size {
+        if let BucketNode::Field(name) = nodes[position] {
+            nodes[position] = BucketNode::StringRef(name);
+        }
+        position += 1;
+        position += value_len(nodes, position).ok_or(Failure::Operation("a map entry has no value"))?;
+    }
+    Ok(())
+}
+
+/// change the number of elements (or entries) of a container
+fn recount(nodes: &mut [BucketNode], container: usize, added: bool) {
+    if let BucketNode::Seq(size, _) | BucketNode::Map(size, _) = &mut nodes[container] {
+        if added { *size += 1 } else { *size -= 1 }
+    }
+}
+
+/// split a path into it's parent, and the container holding the parent's children
+fn parent<'p>(nodes: &[BucketNode], path: &'p BucketPath) -> Result<Option<(Vec<usize>, &'p PathSegment)>, Failure> {
+    let Some((last, parent)) = path.segments.split_last() else {
+        return Ok(None);
+    };
+    let target = resolve(nodes, parent).map_err(|len| missing(path, len))?;
+    let container = container_at(nodes, target.index).ok_or_else(|| missing(path, parent.len()))?;
+    let mut ancestors = target.ancestors;
+    ancestors.push(container);
+    Ok(Some((ancestors, last)))
+}
+
+fn add<'a>(nodes: &mut Vec<BucketNode<'a>>, path: &BucketPath, value: Vec<BucketNode<'a>>) -> Result<(), Failure> {
+    let Some((ancestors, last)) = parent(nodes, path)? else {
+        *nodes = value;
+        return Ok(());
+    };
+    let container = *ancestors.last().unwrap();
+    match nodes[container] {
+        BucketNode::Seq(size, span) => {
+            let index = match last {
+                PathSegment::Key(key) if key == "-" => size,
+                PathSegment::Key(key) => parse_index(key).ok_or_else(|| Failure::Missing(path.clone()))?,
+                PathSegment::Index(index) => *index,
+            };
+            let position = match index {
+                index if index == size => container + 1 + span,
+                index if index < size => find_child(nodes, container, &PathSegment::Index(index)).ok_or_else(|| Failure::Missing(path.clone()))?.0,
+                _ => return Err(Failure::Missing(path.clone())),
+            };
+            resize(nodes, &ancestors, value.len(), 0)?;
+            recount(nodes, container, true);
+            nodes.splice(position..position, value);
+        }
+        BucketNode::Map(_, span) => match find_child(nodes, container, last) {
+            Some((_, position)) => {
+                let len = value_len(nodes, position).ok_or_else(|| Failure::Missing(path.clone()))?;
+                resize(nodes, &ancestors, value.len(), len)?;
+                nodes.splice(position..position + len, value);
+            }
+            None => {
+                let key = match last {
+                    PathSegment::Key(key) => BucketNode::String(key.clone()),
+                    PathSegment::Index(index) => BucketNode::String(index.to_string()),
+                };
+                // a struct only has `Field` keys, so becomes a map
+                let mut ancestors = ancestors;
+                unname(nodes, &mut ancestors)?;
+                let container = *ancestors.last().unwrap();
+                let position = container + 1 + span;
+                resize(nodes, &ancestors, value.len() + 1, 0)?;
+                recount(nodes, container, true);
+                nodes.splice(position..position, Some(key).into_iter().chain(value));
+            }
+        },
+        _ => unreachable!(),
+    }
+    Ok(())
+}
+
+/// remove a value (and it's key), returning it
+fn remove<'a>(nodes: &mut Vec<BucketNode<'a>>, path: &BucketPath) -> Result<Vec<BucketNode<'a>>, Failure> {
+    let Some((ancestors, last)) = parent(nodes, path)? else {
+        return Err(Failure::Operation("can't remove the root"));
+    };
+    let container = *ancestors.last().unwrap();
+    let (key, value) = find_child(nodes, container, last).ok_or_else(|| Failure::Missing(path.clone()))?;
+    let end = value + value_len(nodes, value).ok_or_else(|| Failure::Missing(path.clone()))?;
+    resize(nodes, &ancestors, 0, end - key)?;
+    recount(nodes, container, false);
+    let mut removed: Vec<BucketNode> = nodes.drain(key..end).collect();
+    Ok(removed.split_off(value - key))
+}
+
+fn replace<'a>(nodes: &mut Vec<BucketNode<'a>>, path: &BucketPath, value: Vec<BucketNode<'a>>) -> Result<(), Failure> {
+    let target = resolve(nodes, &path.segments).map_err(|len| missing(path, len))?;
+    resize(nodes, &target.ancestors, value.len(), target.end - target.index)?;
+    nodes.splice(target.index..target.end, value);
+    Ok(())
+}
+
+/// equality of values as JSON - comparing numbers by value, and ignoring the order of map entries,
+/// the names of structs and `Some` wrappers
+fn json_eq(a: &[BucketNode], b: &[BucketNode]) -> bool {
+    let mut a = plain(a, true);
+    let mut b = plain(b, true);
+    canonicalize(&mut a);
+    canonicalize(&mut b);
+    eq_value(&a, &b)
+}
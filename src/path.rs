@@ -0,0 +1,256 @@
+use std::error::Error;
+use std::fmt::{Debug, Display, Formatter, Write};
+use crate::node::BucketNode;
+
+/// A path to a value inside a `Bucket`
+///
+/// Paths can be parsed from two syntaxes (see [`BucketPath::parse`]):
+/// - dotted keys and indexes - `a.b[3]`, with `["key"]` for keys containing other characters
+/// - [JSON Pointer] - `/a/b/3`, with `~0` and `~1` escaping `~` and `/`
+///
+/// [JSON Pointer]: https://www.rfc-editor.org/rfc/rfc6901
+#[derive(Clone, Default, Eq, PartialEq, Hash)]
+pub struct BucketPath {
+    pub(crate) segments: Vec<PathSegment>,
+}
+
+/// A segment of a [`BucketPath`]
+#[derive(Clone, Debug, Eq, PartialEq, Hash)]
+pub enum PathSegment {
+    /// a map key (or struct field, or enum variant)
+    ///
+    /// keys made of digits also index into sequences, as JSON Pointer doesn't distinguish them.
+    Key(String),
+    /// a sequence index (or integer map key)
+    Index(usize),
+}
+
+/// An error from looking up a path in a `Bucket`
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub enum PathError {
+    /// the path couldn't be parsed - the path, and the byte offset of the problem
+    Syntax(String, usize),
+    /// there's no value at the path - the path, up to and including the missing segment
+    Missing(BucketPath),
+}
+
+impl Error for PathError {}
+
+impl Display for PathError {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        match self {
+            PathError::Syntax(path, offset) => write!(f, "invalid path `{}` at offset {}", path, offset),
+            PathError::Missing(path) => write!(f, "no value at path `{}`", path),
+        }
+    }
+}
+
+impl BucketPath {
+    /// the root path (with no segments)
+    pub fn root() -> Self {
+        Self::default()
+    }
+
+    /// parse a path - as a JSON Pointer if it starts with a `/`, otherwise as dotted keys
+    ///
+    /// The empty string is the root path in either syntax.
+    pub fn parse(path: &str) -> Result<Self, PathError> {
+        if path.starts_with('/') {
+            Self::from_pointer(path)
+        } else {
+            Self::from_dotted(path)
+        }
+    }
+
+    /// parse a dotted path, such as `a.b[3]` or `items[0]["content-type"]`
+    pub fn from_dotted(path: &str) -> Result<Self, PathError> {
+        let error = |offset: usize| PathError::Syntax(path.to_owned(), offset);
+        let bytes = path.as_bytes();
+        let mut segments = Vec::new();
+        let mut cursor = 0;
+        while cursor < bytes.len() {
+            match bytes[cursor] {
+                b'[' => {
+                    let start = cursor + 1;
+                    if bytes.get(start) == Some(&b'"') {
+                        let (key, end) = parse_quoted(path, start + 1).ok_or_else(|| error(start))?;
+                        if bytes.get(end) != Some(&b']') {
+                            return Err(error(end));
+                        }
+                        segments.push(PathSegment::Key(key));
+                        cursor = end + 1;
+                    } else {
+                        let end = path[start..].find(']').map(|end| start + end).ok_or_else(|| error(start))?;
+                        let index = parse_index(&path[start..end]).ok_or_else(|| error(start))?;
+                        segments.push(PathSegment::Index(index));
+                        cursor = end + 1;
+                    }
+                }
+                b'.' if cursor != 0 => {
+                    let start = cursor + 1;
+                    let end = path[start..].find(['.', '[', ']']).map_or(path.len(), |end| start + end);
+                    if start == end {
+                        return Err(error(start));
+                    }
+                    segments.push(PathSegment::Key(path[start..end].to_owned()));
+                    cursor = end;
+                }
+                _ if cursor == 0 => {
+                    let end = path.find(['.', '[', ']']).unwrap_or(path.len());
+                    if end == 0 {
+                        return Err(error(0));
+                    }
+                    segments.push(PathSegment::Key(path[..end].to_owned()));
+                    cursor = end;
+                }
+                _ => return Err(error(cursor)),
+            }
+        }
+        Ok(Self { segments })
+    }
+
+    /// parse a JSON Pointer, such as `/a/b/3`
+    pub fn from_pointer(path: &str) -> Result<Self, PathError> {
+        if path.is_empty() {
+            return Ok(Self::root());
+        }
+        let Some(rest) = path.strip_prefix('/') else {
+            return Err(PathError::Syntax(path.to_owned(), 0));
+        };
+        let mut segments = Vec::new();
+        let mut offset = 1;
+        for token in rest.split('/') {
+            let mut key = String::with_capacity(token.len());
+            let mut chars = token.char_indices();
+            while let Some((index, char)) = chars.next() {
+                match char {
+                    '~' => match chars.next() {
+                        Some((_, '0')) => key.push('~'),
+                        Some((_, '1')) => key.push('/'),
+                        _ => return Err(PathError::Syntax(path.to_owned(), offset + index)),
+                    },
+                    char => key.push(char),
+                }
+            }
+            segments.push(PathSegment::Key(key));
+            offset += token.len() + 1;
+        }
+        Ok(Self { segments })
+    }
+
+    /// the segments of the path
+    pub fn segments(&self) -> &[PathSegment] {
+        &self.segments
+    }
+
+    /// add a key to the end of the path
+    pub fn push_key(&mut self, key: impl Into<String>) {
+        self.segments.push(PathSegment::Key(key.into()));
+    }
+
+    /// add an index to the end of the path
+    pub fn push_index(&mut self, index: usize) {
+        self.segments.push(PathSegment::Index(index));
+    }
+
+    /// remove the last segment of the path
+    pub fn pop(&mut self) -> Option<PathSegment> {
+        self.segments.pop()
+    }
+
+    /// format the path as a JSON Pointer
+    pub fn to_pointer(&self) -> String {
+        let mut pointer = String::new();
+        for segment in &self.segments {
+            pointer.push('/');
+            match segment {
+                PathSegment::Key(key) => {
+                    for char in key.chars() {
+                        match char {
+                            '~' => pointer.push_str("~0"),
+                            '/' => pointer.push_str("~1"),
+                            char => pointer.push(char),
+                        }
+                    }
+                }
+                PathSegment::Index(index) => { let _ = write!(pointer, "{}", index); }
+            }
+        }
+        pointer
+    }
+}
+
+/// parse a sequence index - digits only, without leading zeros
+pub(crate) fn parse_index(index: &str) -> Option<usize> {
+    if index.is_empty() || !index.bytes().all(|b| b.is_ascii_digit()) || (index.len() > 1 && index.starts_with('0')) {
+        return None;
+    }
+    index.parse().ok()
+}
+
+/// the path segment for a map key (or enum variant identifier)
+pub(crate) fn key_segment(key: Option<&BucketNode>) -> PathSegment {
+    match key {
+        Some(BucketNode::U8(val)) => PathSegment::Index(*val as usize),
+        Some(BucketNode::U16(val)) => PathSegment::Index(*val as usize),
+        Some(BucketNode::U32(val)) => PathSegment::Index(*val as usize),
+        Some(BucketNode::U64(val)) => usize::try_from(*val).map_or_else(|_| PathSegment::Key(val.to_string()), PathSegment::Index),
+        Some(BucketNode::I8(val)) => PathSegment::Key(val.to_string()),
+        Some(BucketNode::I16(val)) => PathSegment::Key(val.to_string()),
+        Some(BucketNode::I32(val)) => PathSegment::Key(val.to_string()),
+        Some(BucketNode::I64(val)) => PathSegment::Key(val.to_string()),
+        Some(BucketNode::Bool(val)) => PathSegment::Key(val.to_string()),
+        Some(BucketNode::Char(val)) => PathSegment::Key(val.to_string()),
+        Some(node) => PathSegment::Key(node.as_str().unwrap_or("?").to_owned()),
+        None => PathSegment::Key("?".to_owned()),
+    }
+}
+
+/// parse a quoted key starting after it's opening quote - returns the key and the offset after the closing quote
+fn parse_quoted(path: &str, start: usize) -> Option<(String, usize)> {
+    let mut key = String::new();
+    let mut chars = path[start..].char_indices();
+    while let Some((index, char)) = chars.next() {
+        match char {
+            '"' => return Some((key, start + index + 1)),
+            '\\' => key.push(chars.next()?.1),
+            char => key.push(char),
+        }
+    }
+    None
+}
+
+/// if a key can be written in a dotted path without quoting
+fn is_plain_key(key: &str) -> bool {
+    !key.is_empty() && !key.contains(['.', '[', ']', '"', '\\']) && !key.starts_with('/')
+}
+
+impl Display for BucketPath {
+    /// formats the path in it's dotted syntax
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        for (i, segment) in self.segments.iter().enumerate() {
+            match segment {
+                PathSegment::Key(key) if is_plain_key(key) => {
+                    if i != 0 { f.write_char('.')?; }
+                    f.write_str(key)?;
+                }
+                PathSegment::Key(key) => {
+                    f.write_str("[\"")?;
+                    for char in key.chars() {
+                        if char == '"' || char == '\\' { f.write_char('\\')?; }
+                        f.write_char(char)?;
+                    }
+                    f.write_str("\"]")?;
+                }
+                PathSegment::Index(index) => write!(f, "[{}]", index)?,
+            }
+        }
+        Ok(())
+    }
+}
+
+impl Debug for BucketPath {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        Display::fmt(self, f)
+    }
+}
@@ -1,68 +1,142 @@
-use std::marker::PhantomData;
+use std::mem;
 use serde::Deserialize;
-use crate::BucketDeserializer;
-use crate::node::BucketNode;
+use crate::de::{BucketBuffer, BucketConfig, BucketDeserializer};
+use crate::node::{BucketNode, container, value_len};
+use crate::view::BucketRef;
 
-fn skip_item(offset: &mut usize, slice: &mut [BucketNode]) {
-    while offset >= &slice.len() {
-        match slice[offset] {
-
-        }
-    }
+/// A borrowed segment of a `Bucket`
+///
+/// Holds the nodes of a single value, which can be indexed into (for sequences and maps)
+/// or deserialized on it's own, leaving the rest of the bucket untouched.
+pub struct BucketSegment<'r, 'a> {
+    pub(crate) slice: &'r mut [BucketNode<'a>],
+    pub(crate) config: BucketConfig,
 }
 
-pub struct BucketSegment<'r, 'a> {
-    slice: &'a mut [BucketNode<'a>]
+/// split the value at the front of `slice` from the rest
+fn split_item<'r, 'a>(slice: &mut &'r mut [BucketNode<'a>]) -> Option<&'r mut [BucketNode<'a>]> {
+    let len = value_len(slice, 0)?;
+    let (item, rest) = mem::take(slice).split_at_mut(len);
+    *slice = rest;
+    Some(item)
 }
 
 impl<'r, 'a> BucketSegment<'r, 'a> {
-    pub fn try_seq_index(self, index: usize) -> Option<BucketSegment<'r, 'a>> {
-        if let BucketNode::Seq(size) = self.slice.get(0)? {
-            if index >= *size {
-                return None;
-            }
+    /// get the element at `index` of a sequence
+    ///
+    /// Returns `None` if this isn't a sequence or the index is out-of-bounds.
+    pub fn seq_index(self, index: usize) -> Option<BucketSegment<'r, 'a>> {
+        self.iter_seq()?.nth(index)
+    }
 
+    /// get the value of a map for a given key
+    ///
+    /// Only string keys are matched. Returns `None` if this isn't a map or the key is missing.
+    pub fn map_get(self, key: &str) -> Option<BucketSegment<'r, 'a>> {
+        self.iter_map()?
+            .find(|(k, _)| k.slice.first().and_then(BucketNode::as_str) == Some(key))
+            .map(|(_, value)| value)
+    }
 
-        }
+    /// iterate over the elements of a sequence
+    ///
+    /// Returns `None` if this isn't a sequence.
+    pub fn iter_seq(self) -> Option<BucketSeqIter<'r, 'a>> {
+        let offset = container(self.slice);
+        let BucketNode::Seq(size, _) = self.slice.get(offset)? else {
+            return None;
+        };
+        let size = *size;
+        Some(BucketSeqIter {
+            rest: &mut self.slice[offset + 1..],
+            size,
+            config: self.config,
+        })
+    }
+
+    /// iterate over the entries (key and value) of a map
+    ///
+    /// Returns `None` if this isn't a map.
+    pub fn iter_map(self) -> Option<BucketMapIter<'r, 'a>> {
+        let offset = container(self.slice);
+        let BucketNode::Map(size, _) = self.slice.get(offset)? else {
+            return None;
+        };
+        let size = *size;
+        Some(BucketMapIter {
+            rest: &mut self.slice[offset + 1..],
+            size,
+            config: self.config,
+        })
     }
 
+    /// get a read-only view of the segment's value
+    pub fn view(&self) -> BucketRef<'_> {
+        BucketRef::item(self.slice, self.config)
+    }
 
     /// deserialize into (clone method)
     ///
-    /// This function deserializes into the given type, cloning owned data (Vec, String)
+    /// This function deserializes into the given type, borrowing owned data (Vec, String)
+    /// from the bucket - types that need ownership will clone it.
     /// - `T` as `Deserialize`
     /// - `E` as a `serde::de::Error`
-    pub fn deserialize_into_clone<T: Deserialize<'a>, E: serde::de::Error>(&'a mut self) -> Result<T, E> {
-        T::deserialize(&mut BucketDeserializer {
-            buffer: &mut self.slice,
-            cursor: 0,
-            error: PhantomData::<E>::default(),
-            clone: true,
-        })
+    pub fn deserialize_into_clone<'x, T: Deserialize<'x>, E: serde::de::Error>(&'x self) -> Result<T, E> {
+        T::deserialize(&mut BucketDeserializer::new(BucketBuffer::Ref(self.slice), self.config))
     }
 
-    /// deserialize into (clone method)
+    /// deserialize into
     ///
     /// This function deserializes into the given type, taking/replacing owned data (Vec, String)
     /// - `T` as `Deserialize`
     /// - `E` as a `serde::de::Error`
-    pub fn deserialize_into<T: Deserialize<'a>, E: serde::de::Error>(&'a mut self) -> Result<T, E> {
-        T::deserialize(&mut BucketDeserializer {
-            buffer: &mut self.slice,
-            cursor: 0,
-            error: PhantomData::<E>::default(),
-            clone: false,
-        })
+    pub fn deserialize_into<T: Deserialize<'a>, E: serde::de::Error>(&mut self) -> Result<T, E> {
+        T::deserialize(&mut BucketDeserializer::new(BucketBuffer::Mut(self.slice), self.config))
     }
 
     #[cfg(feature="deserializer")]
     /// get a 'deserializer' for custom deserialization
-    pub fn deserializer<E: serde::de::Error>(&'a mut self) -> BucketDeserializer<'a, E> {
-        BucketDeserializer {
-            buffer: &mut self.slice,
-            cursor: 0,
-            error: PhantomData::<E>::default(),
-            clone: false,
+    pub fn deserializer<E: serde::de::Error>(&mut self) -> BucketDeserializer<'_, 'a, E> {
+        BucketDeserializer::new(BucketBuffer::Mut(self.slice), self.config)
+    }
+}
+
+/// An iterator over the elements of a sequence segment
+pub struct BucketSeqIter<'r, 'a> {
+    rest: &'r mut [BucketNode<'a>],
+    size: usize,
+    config: BucketConfig,
+}
+
+impl<'r, 'a> Iterator for BucketSeqIter<'r, 'a> {
+    type Item = BucketSegment<'r, 'a>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        if self.size == 0 {
+            return None;
         }
+        self.size -= 1;
+        Some(BucketSegment { slice: split_item(&mut self.rest)?, config: self.config })
     }
-}
\ No newline at end of file
+}
+
+/// An iterator over the entries of a map segment
+pub struct BucketMapIter<'r, 'a> {
+    rest: &'r mut [BucketNode<'a>],
+    size: usize,
+    config: BucketConfig,
+}
+
+impl<'r, 'a> Iterator for BucketMapIter<'r, 'a> {
+    type Item = (BucketSegment<'r, 'a>, BucketSegment<'r, 'a>);
+
+    fn next(&mut self) -> Option<Self::Item> {
+        if self.size == 0 {
+            return None;
+        }
+        self.size -= 1;
+        let key = split_item(&mut self.rest)?;
+        let value = split_item(&mut self.rest)?;
+        Some((BucketSegment { slice: key, config: self.config }, BucketSegment { slice: value, config: self.config }))
+    }
+}
@@ -1,6 +1,493 @@
-use serde::Serialize;
+use std::cell::Cell;
+use std::marker::PhantomData;
+use serde::{Serialize, Serializer};
+use serde::ser::{Error, SerializeMap, SerializeSeq, SerializeStruct, SerializeStructVariant, SerializeTuple, SerializeTupleStruct, SerializeTupleVariant};
 use crate::Bucket;
+use crate::fault::Fault;
+use crate::node::{BucketNode, VariantKind, value_len};
 
-impl Serialize for BucketSegement<'a> {
+impl<'a> Serialize for Bucket<'a> {
+    fn serialize<S>(&self, serializer: S) -> Result<S::Ok, S::Error> where S: Serializer {
+        let cursor = Cell::new(0);
+        BucketNodeSerializer {
+            nodes: &self.inner,
+            cursor: &cursor,
+        }.serialize(serializer)
+    }
+}
 
-}
\ No newline at end of file
+/// serializes the node under the cursor (and its children), moving the cursor past it.
+///
+/// child values are serialized by passing the same `BucketNodeSerializer` back into the
+/// serializer, which works as each element/key/value is serialized exactly once, in order.
+struct BucketNodeSerializer<'r, 'a> {
+    nodes: &'r [BucketNode<'a>],
+    cursor: &'r Cell<usize>,
+}
+
+impl<'r, 'a> Serialize for BucketNodeSerializer<'r, 'a> {
+    fn serialize<S>(&self, serializer: S) -> Result<S::Ok, S::Error> where S: Serializer {
+        let Some(node) = self.nodes.get(self.cursor.get()) else {
+            return Err(S::Error::custom(Fault::OutOfBounds));
+        };
+        self.cursor.set(self.cursor.get() + 1);
+
+        match node {
+            BucketNode::Consumed => Err(S::Error::custom(Fault::Consumed)),
+            BucketNode::Unsized => Err(S::Error::custom(Fault::MissingSize)),
+
+            BucketNode::Unit => serializer.serialize_unit(),
+            BucketNode::Bool(val) => serializer.serialize_bool(*val),
+            BucketNode::Char(val) => serializer.serialize_char(*val),
+
+            BucketNode::U8(val) => serializer.serialize_u8(*val),
+            BucketNode::I8(val) => serializer.serialize_i8(*val),
+            BucketNode::U16(val) => serializer.serialize_u16(*val),
+            BucketNode::I16(val) => serializer.serialize_i16(*val),
+            BucketNode::U32(val) => serializer.serialize_u32(*val),
+            BucketNode::I32(val) => serializer.serialize_i32(*val),
+            BucketNode::U64(val) => serializer.serialize_u64(*val),
+            BucketNode::I64(val) => serializer.serialize_i64(*val),
+            BucketNode::U128(val) => serializer.serialize_u128(*val),
+            BucketNode::I128(val) => serializer.serialize_i128(*val),
+            BucketNode::F32(val) => serializer.serialize_f32(*val),
+            BucketNode::F64(val) => serializer.serialize_f64(*val),
+
+            BucketNode::String(val) => serializer.serialize_str(val),
+            BucketNode::StringRef(val) => serializer.serialize_str(val),
+
+            BucketNode::Bytes(val) => serializer.serialize_bytes(val),
+            BucketNode::BytesRef(val) => serializer.serialize_bytes(val),
+
+            BucketNode::None => serializer.serialize_none(),
+            BucketNode::Some => serializer.serialize_some(self),
+
+            BucketNode::NewType => serializer.serialize_newtype_struct("", self),
+
+            BucketNode::UnitStruct(name) => serializer.serialize_unit_struct(name),
+            BucketNode::NewTypeStruct(name) => serializer.serialize_newtype_struct(name, self),
+            BucketNode::TupleStruct(name) => {
+                let size = self.seq_header()?;
+                let mut seq = serializer.serialize_tuple_struct(name, size)?;
+                for _ in 0..size {
+                    seq.serialize_field(self)?;
+                }
+                seq.end()
+            }
+            BucketNode::Struct(name) => {
+                let size = self.map_header()?;
+                let mut map = serializer.serialize_struct(name, size)?;
+                for _ in 0..size {
+                    map.serialize_field(self.field()?, self)?;
+                }
+                map.end()
+            }
+            BucketNode::Field(name) => serializer.serialize_str(name),
+
+            BucketNode::Variant(name, index, variant, VariantKind::Unit) => {
+                serializer.serialize_unit_variant(name, *index, variant)
+            }
+            BucketNode::Variant(name, index, variant, VariantKind::NewType) => {
+                serializer.serialize_newtype_variant(name, *index, variant, self)
+            }
+            BucketNode::Variant(name, index, variant, VariantKind::Tuple) => {
+                let size = self.seq_header()?;
+                let mut seq = serializer.serialize_tuple_variant(name, *index, variant, size)?;
+                for _ in 0..size {
+                    seq.serialize_field(self)?;
+                }
+                seq.end()
+            }
+            BucketNode::Variant(name, index, variant, VariantKind::Struct) => {
+                let size = self.map_header()?;
+                let mut map = serializer.serialize_struct_variant(name, *index, variant, size)?;
+                for _ in 0..size {
+                    map.serialize_field(self.field()?, self)?;
+                }
+                map.end()
+            }
+            // the names of a captured enum aren't known statically, so it is replayed
+            // externally tagged - as a map of the variant identifier to it's payload,
+            // or just the identifier for a unit variant
+            BucketNode::Enum if self.unit_payload() => {
+                let value = self.serialize(serializer)?;
+                self.cursor.set(self.cursor.get() + 1);
+                Ok(value)
+            }
+            BucketNode::Enum => {
+                let mut map = serializer.serialize_map(Some(1))?;
+                map.serialize_key(self)?;
+                map.serialize_value(self)?;
+                map.end()
+            }
+
+            BucketNode::Seq(size, _) => {
+                let mut seq = serializer.serialize_seq(Some(*size))?;
+                for _ in 0..*size {
+                    seq.serialize_element(self)?;
+                }
+                seq.end()
+            }
+            BucketNode::Map(size, _) => {
+                let mut map = serializer.serialize_map(Some(*size))?;
+                for _ in 0..*size {
+                    map.serialize_key(self)?;
+                    map.serialize_value(self)?;
+                }
+                map.end()
+            }
+        }
+    }
+}
+
+impl<'r, 'a> BucketNodeSerializer<'r, 'a> {
+    /// read the `Seq` following a tuple struct/variant, returning it's size
+    fn seq_header<E: Error>(&self) -> Result<usize, E> {
+        match self.nodes.get(self.cursor.get()) {
+            Some(BucketNode::Seq(size, _)) => {
+                self.cursor.set(self.cursor.get() + 1);
+                Ok(*size)
+            }
+            _ => Err(E::custom(Fault::Malformed("expected tuple fields"))),
+        }
+    }
+
+    /// read the `Map` following a struct/struct variant, returning it's size
+    fn map_header<E: Error>(&self) -> Result<usize, E> {
+        match self.nodes.get(self.cursor.get()) {
+            Some(BucketNode::Map(size, _)) => {
+                self.cursor.set(self.cursor.get() + 1);
+                Ok(*size)
+            }
+            _ => Err(E::custom(Fault::Malformed("expected struct fields"))),
+        }
+    }
+
+    /// if the captured enum under the cursor was a unit variant
+    fn unit_payload(&self) -> bool {
+        let identifier = self.cursor.get();
+        value_len(self.nodes, identifier)
+            .is_some_and(|len| matches!(self.nodes.get(identifier + len), Some(BucketNode::Unit)))
+    }
+
+    /// read a struct field name
+    fn field<E: Error>(&self) -> Result<&'static str, E> {
+        match self.nodes.get(self.cursor.get()) {
+            Some(BucketNode::Field(name)) => {
+                self.cursor.set(self.cursor.get() + 1);
+                Ok(name)
+            }
+            _ => Err(E::custom(Fault::Malformed("expected struct field name"))),
+        }
+    }
+}
+
+/// A Serde Serializer that captures values into bucket nodes
+///
+/// Struct and enum names are kept, so they replay exactly as they were serialized.
+pub struct BucketSerializer<'t, 'a, E> where E: serde::ser::Error {
+    pub(crate) target: &'t mut Vec<BucketNode<'a>>,
+    pub(crate) human_readable: bool,
+    pub(crate) error: PhantomData<E>,
+}
+
+impl<'t, 'a, E> BucketSerializer<'t, 'a, E> where E: serde::ser::Error {
+    fn push(&mut self, node: BucketNode<'a>) -> Result<(), E> {
+        self.target.push(node);
+        Ok(())
+    }
+
+    /// push a placeholder for a container, returning it's position
+    fn open(&mut self) -> usize {
+        self.target.push(BucketNode::Unsized);
+        self.target.len() - 1
+    }
+}
+
+impl<'x, 't, 'a, E> Serializer for &'x mut BucketSerializer<'t, 'a, E> where E: serde::ser::Error {
+    type Ok = ();
+    type Error = E;
+
+    type SerializeSeq = BucketSeqSerializer<'x, 't, 'a, E>;
+    type SerializeTuple = BucketSeqSerializer<'x, 't, 'a, E>;
+    type SerializeTupleStruct = BucketSeqSerializer<'x, 't, 'a, E>;
+    type SerializeTupleVariant = BucketSeqSerializer<'x, 't, 'a, E>;
+    type SerializeMap = BucketMapSerializer<'x, 't, 'a, E>;
+    type SerializeStruct = BucketMapSerializer<'x, 't, 'a, E>;
+    type SerializeStructVariant = BucketMapSerializer<'x, 't, 'a, E>;
+
+    fn is_human_readable(&self) -> bool {
+        self.human_readable
+    }
+
+    fn serialize_bool(self, v: bool) -> Result<Self::Ok, Self::Error> {
+        self.push(BucketNode::Bool(v))
+    }
+
+    fn serialize_i8(self, v: i8) -> Result<Self::Ok, Self::Error> {
+        self.push(BucketNode::I8(v))
+    }
+
+    fn serialize_i16(self, v: i16) -> Result<Self::Ok, Self::Error> {
+        self.push(BucketNode::I16(v))
+    }
+
+    fn serialize_i32(self, v: i32) -> Result<Self::Ok, Self::Error> {
+        self.push(BucketNode::I32(v))
+    }
+
+    fn serialize_i64(self, v: i64) -> Result<Self::Ok, Self::Error> {
+        self.push(BucketNode::I64(v))
+    }
+
+    fn serialize_i128(self, v: i128) -> Result<Self::Ok, Self::Error> {
+        self.push(BucketNode::I128(v))
+    }
+
+    fn serialize_u8(self, v: u8) -> Result<Self::Ok, Self::Error> {
+        self.push(BucketNode::U8(v))
+    }
+
+    fn serialize_u16(self, v: u16) -> Result<Self::Ok, Self::Error> {
+        self.push(BucketNode::U16(v))
+    }
+
+    fn serialize_u32(self, v: u32) -> Result<Self::Ok, Self::Error> {
+        self.push(BucketNode::U32(v))
+    }
+
+    fn serialize_u64(self, v: u64) -> Result<Self::Ok, Self::Error> {
+        self.push(BucketNode::U64(v))
+    }
+
+    fn serialize_u128(self, v: u128) -> Result<Self::Ok, Self::Error> {
+        self.push(BucketNode::U128(v))
+    }
+
+    fn serialize_f32(self, v: f32) -> Result<Self::Ok, Self::Error> {
+        self.push(BucketNode::F32(v))
+    }
+
+    fn serialize_f64(self, v: f64) -> Result<Self::Ok, Self::Error> {
+        self.push(BucketNode::F64(v))
+    }
+
+    fn serialize_char(self, v: char) -> Result<Self::Ok, Self::Error> {
+        self.push(BucketNode::Char(v))
+    }
+
+    fn serialize_str(self, v: &str) -> Result<Self::Ok, Self::Error> {
+        self.push(BucketNode::String(v.to_owned()))
+    }
+
+    fn serialize_bytes(self, v: &[u8]) -> Result<Self::Ok, Self::Error> {
+        self.push(BucketNode::Bytes(v.to_vec()))
+    }
+
+    fn serialize_none(self) -> Result<Self::Ok, Self::Error> {
+        self.push(BucketNode::None)
+    }
+
+    fn serialize_some<T>(self, value: &T) -> Result<Self::Ok, Self::Error> where T: ?Sized + Serialize {
+        self.push(BucketNode::Some)?;
+        value.serialize(self)
+    }
+
+    fn serialize_unit(self) -> Result<Self::Ok, Self::Error> {
+        self.push(BucketNode::Unit)
+    }
+
+    fn serialize_unit_struct(self, name: &'static str) -> Result<Self::Ok, Self::Error> {
+        self.push(BucketNode::UnitStruct(name))
+    }
+
+    fn serialize_unit_variant(self, name: &'static str, variant_index: u32, variant: &'static str) -> Result<Self::Ok, Self::Error> {
+        self.push(BucketNode::Variant(name, variant_index, variant, VariantKind::Unit))
+    }
+
+    fn serialize_newtype_struct<T>(self, name: &'static str, value: &T) -> Result<Self::Ok, Self::Error> where T: ?Sized + Serialize {
+        self.push(BucketNode::NewTypeStruct(name))?;
+        value.serialize(self)
+    }
+
+    fn serialize_newtype_variant<T>(self, name: &'static str, variant_index: u32, variant: &'static str, value: &T) -> Result<Self::Ok, Self::Error> where T: ?Sized + Serialize {
+        self.push(BucketNode::Variant(name, variant_index, variant, VariantKind::NewType))?;
+        value.serialize(self)
+    }
+
+    fn serialize_seq(self, _len: Option<usize>) -> Result<Self::SerializeSeq, Self::Error> {
+        Ok(BucketSeqSerializer {
+            position: self.open(),
+            size: 0,
+            bucket: self,
+        })
+    }
+
+    fn serialize_tuple(self, len: usize) -> Result<Self::SerializeTuple, Self::Error> {
+        self.serialize_seq(Some(len))
+    }
+
+    fn serialize_tuple_struct(self, name: &'static str, len: usize) -> Result<Self::SerializeTupleStruct, Self::Error> {
+        self.push(BucketNode::TupleStruct(name))?;
+        self.serialize_seq(Some(len))
+    }
+
+    fn serialize_tuple_variant(self, name: &'static str, variant_index: u32, variant: &'static str, len: usize) -> Result<Self::SerializeTupleVariant, Self::Error> {
+        self.push(BucketNode::Variant(name, variant_index, variant, VariantKind::Tuple))?;
+        self.serialize_seq(Some(len))
+    }
+
+    fn serialize_map(self, _len: Option<usize>) -> Result<Self::SerializeMap, Self::Error> {
+        Ok(BucketMapSerializer {
+            position: self.open(),
+            size: 0,
+            bucket: self,
+        })
+    }
+
+    fn serialize_struct(self, name: &'static str, len: usize) -> Result<Self::SerializeStruct, Self::Error> {
+        self.push(BucketNode::Struct(name))?;
+        self.serialize_map(Some(len))
+    }
+
+    fn serialize_struct_variant(self, name: &'static str, variant_index: u32, variant: &'static str, len: usize) -> Result<Self::SerializeStructVariant, Self::Error> {
+        self.push(BucketNode::Variant(name, variant_index, variant, VariantKind::Struct))?;
+        self.serialize_map(Some(len))
+    }
+}
+
+pub struct BucketSeqSerializer<'x, 't, 'a, E> where E: serde::ser::Error {
+    bucket: &'x mut BucketSerializer<'t, 'a, E>,
+    position: usize,
+    size: usize,
+}
+
+impl<'x, 't, 'a, E> BucketSeqSerializer<'x, 't, 'a, E> where E: serde::ser::Error {
+    fn element<T>(&mut self, value: &T) -> Result<(), E> where T: ?Sized + Serialize {
+        value.serialize(&mut *self.bucket)?;
+        self.size += 1;
+        Ok(())
+    }
+
+    fn close(self) -> Result<(), E> {
+        self.bucket.target[self.position] = BucketNode::Seq(self.size, self.bucket.target.len() - self.position - 1);
+        Ok(())
+    }
+}
+
+impl<'x, 't, 'a, E> SerializeSeq for BucketSeqSerializer<'x, 't, 'a, E> where E: serde::ser::Error {
+    type Ok = ();
+    type Error = E;
+
+    fn serialize_element<T>(&mut self, value: &T) -> Result<(), Self::Error> where T: ?Sized + Serialize {
+        self.element(value)
+    }
+
+    fn end(self) -> Result<Self::Ok, Self::Error> {
+        self.close()
+    }
+}
+
+impl<'x, 't, 'a, E> SerializeTuple for BucketSeqSerializer<'x, 't, 'a, E> where E: serde::ser::Error {
+    type Ok = ();
+    type Error = E;
+
+    fn serialize_element<T>(&mut self, value: &T) -> Result<(), Self::Error> where T: ?Sized + Serialize {
+        self.element(value)
+    }
+
+    fn end(self) -> Result<Self::Ok, Self::Error> {
+        self.close()
+    }
+}
+
+impl<'x, 't, 'a, E> SerializeTupleStruct for BucketSeqSerializer<'x, 't, 'a, E> where E: serde::ser::Error {
+    type Ok = ();
+    type Error = E;
+
+    fn serialize_field<T>(&mut self, value: &T) -> Result<(), Self::Error> where T: ?Sized + Serialize {
+        self.element(value)
+    }
+
+    fn end(self) -> Result<Self::Ok, Self::Error> {
+        self.close()
+    }
+}
+
+impl<'x, 't, 'a, E> SerializeTupleVariant for BucketSeqSerializer<'x, 't, 'a, E> where E: serde::ser::Error {
+    type Ok = ();
+    type Error = E;
+
+    fn serialize_field<T>(&mut self, value: &T) -> Result<(), Self::Error> where T: ?Sized + Serialize {
+        self.element(value)
+    }
+
+    fn end(self) -> Result<Self::Ok, Self::Error> {
+        self.close()
+    }
+}
+
+pub struct BucketMapSerializer<'x, 't, 'a, E> where E: serde::ser::Error {
+    bucket: &'x mut BucketSerializer<'t, 'a, E>,
+    position: usize,
+    size: usize,
+}
+
+impl<'x, 't, 'a, E> BucketMapSerializer<'x, 't, 'a, E> where E: serde::ser::Error {
+    fn field<T>(&mut self, key: &'static str, value: &T) -> Result<(), E> where T: ?Sized + Serialize {
+        self.bucket.push(BucketNode::Field(key))?;
+        value.serialize(&mut *self.bucket)?;
+        self.size += 1;
+        Ok(())
+    }
+
+    fn close(self) -> Result<(), E> {
+        self.bucket.target[self.position] = BucketNode::Map(self.size, self.bucket.target.len() - self.position - 1);
+        Ok(())
+    }
+}
+
+impl<'x, 't, 'a, E> SerializeMap for BucketMapSerializer<'x, 't, 'a, E> where E: serde::ser::Error {
+    type Ok = ();
+    type Error = E;
+
+    fn serialize_key<T>(&mut self, key: &T) -> Result<(), Self::Error> where T: ?Sized + Serialize {
+        key.serialize(&mut *self.bucket)
+    }
+
+    fn serialize_value<T>(&mut self, value: &T) -> Result<(), Self::Error> where T: ?Sized + Serialize {
+        value.serialize(&mut *self.bucket)?;
+        self.size += 1;
+        Ok(())
+    }
+
+    fn end(self) -> Result<Self::Ok, Self::Error> {
+        self.close()
+    }
+}
+
+impl<'x, 't, 'a, E> SerializeStruct for BucketMapSerializer<'x, 't, 'a, E> where E: serde::ser::Error {
+    type Ok = ();
+    type Error = E;
+
+    fn serialize_field<T>(&mut self, key: &'static str, value: &T) -> Result<(), Self::Error> where T: ?Sized + Serialize {
+        self.field(key, value)
+    }
+
+    fn end(self) -> Result<Self::Ok, Self::Error> {
+        self.close()
+    }
+}
+
+impl<'x, 't, 'a, E> SerializeStructVariant for BucketMapSerializer<'x, 't, 'a, E> where E: serde::ser::Error {
+    type Ok = ();
+    type Error = E;
+
+    fn serialize_field<T>(&mut self, key: &'static str, value: &T) -> Result<(), Self::Error> where T: ?Sized + Serialize {
+        self.field(key, value)
+    }
+
+    fn end(self) -> Result<Self::Ok, Self::Error> {
+        self.close()
+    }
+}
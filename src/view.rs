@@ -0,0 +1,568 @@
+use std::fmt::{Debug, Formatter};
+use std::hash::{Hash, Hasher};
+use serde::Deserialize;
+use crate::de::{BucketBuffer, BucketConfig, BucketDeserializer};
+use crate::debug::debug_nodes;
+use crate::fault::Fault;
+use crate::node::{BucketNode, VariantKind, container, eq_numeric, eq_unordered, value_len};
+use crate::path::{BucketPath, PathError, PathSegment, parse_index};
+
+/// A read-only view of a value in a `Bucket`
+///
+/// Borrows the nodes of a single value, which can be inspected (with [`value`] or the `as_*`
+/// methods), indexed into, or deserialized, without copying anything out of the bucket.
+///
+/// [`value`]: BucketRef::value
+#[derive(Copy, Clone)]
+pub struct BucketRef<'r> {
+    pub(crate) slice: &'r [BucketNode<'r>],
+    pub(crate) config: BucketConfig,
+}
+
+/// The shape of a value in a `Bucket`, as returned by [`BucketRef::kind`]
+#[derive(Copy, Clone, Debug, Eq, PartialEq, Hash)]
+pub enum BucketKind {
+    Unit,
+    Bool,
+    Char,
+    UInt,
+    Int,
+    Float,
+    String,
+    Bytes,
+    None,
+    Some,
+    Seq,
+    Map,
+    NewType,
+    UnitStruct,
+    NewTypeStruct,
+    TupleStruct,
+    Struct,
+    Variant,
+    Enum,
+    Empty,
+}
+
+/// A value in a `Bucket`, for matching on
+///
+/// Integers and floats of any width are widened, and borrowed/owned strings and bytes are
+/// both returned as slices.
+#[derive(Copy, Clone, Debug)]
+pub enum ValueRef<'r> {
+    Unit,
+    Bool(bool),
+    Char(char),
+    /// an unsigned integer, of any width
+    UInt(u128),
+    /// a signed integer, of any width
+    Int(i128),
+    /// a float, of any width
+    Float(f64),
+    /// a string (or struct field name)
+    String(&'r str),
+    Bytes(&'r [u8]),
+    None,
+    Some(BucketRef<'r>),
+    Seq(SeqRef<'r>),
+    Map(MapRef<'r>),
+    /// an unnamed newtype
+    NewType(BucketRef<'r>),
+    UnitStruct(&'static str),
+    NewTypeStruct(&'static str, BucketRef<'r>),
+    TupleStruct(&'static str, SeqRef<'r>),
+    Struct(&'static str, MapRef<'r>),
+    /// an enum variant - the enum name, variant index, variant name, and payload (if any)
+    Variant(&'static str, u32, &'static str, Option<BucketRef<'r>>),
+    /// an enum from a data format - the variant identifier and it's payload
+    Enum(BucketRef<'r>, BucketRef<'r>),
+    /// a value that has been taken by [`Bucket::deserialize_into`], or was never completed
+    ///
+    /// [`Bucket::deserialize_into`]: crate::Bucket::deserialize_into
+    Empty,
+}
+
+impl<'r> ValueRef<'r> {
+    /// the shape of this value
+    pub fn kind(&self) -> BucketKind {
+        match self {
+            ValueRef::Unit => BucketKind::Unit,
+            ValueRef::Bool(_) => BucketKind::Bool,
+            ValueRef::Char(_) => BucketKind::Char,
+            ValueRef::UInt(_) => BucketKind::UInt,
+            ValueRef::Int(_) => BucketKind::Int,
+            ValueRef::Float(_) => BucketKind::Float,
+            ValueRef::String(_) => BucketKind::String,
+            ValueRef::Bytes(_) => BucketKind::Bytes,
+            ValueRef::None => BucketKind::None,
+            ValueRef::Some(_) => BucketKind::Some,
+            ValueRef::Seq(_) => BucketKind::Seq,
+            ValueRef::Map(_) => BucketKind::Map,
+            ValueRef::NewType(_) => BucketKind::NewType,
+            ValueRef::UnitStruct(_) => BucketKind::UnitStruct,
+            ValueRef::NewTypeStruct(..) => BucketKind::NewTypeStruct,
+            ValueRef::TupleStruct(..) => BucketKind::TupleStruct,
+            ValueRef::Struct(..) => BucketKind::Struct,
+            ValueRef::Variant(..) => BucketKind::Variant,
+            ValueRef::Enum(..) => BucketKind::Enum,
+            ValueRef::Empty => BucketKind::Empty,
+        }
+    }
+}
+
+impl<'r> Debug for BucketRef<'r> {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        debug_nodes(self.slice, f)
+    }
+}
+
+/// views are equal if their values are, in the same way as [`Bucket`](crate::Bucket)s
+impl<'r> PartialEq for BucketRef<'r> {
+    fn eq(&self, other: &Self) -> bool {
+        self.slice == other.slice
+    }
+}
+
+impl<'r> Eq for BucketRef<'r> {}
+
+impl<'r> Hash for BucketRef<'r> {
+    fn hash<H: Hasher>(&self, state: &mut H) {
+        self.slice.hash(state)
+    }
+}
+
+/// split the value at the front of `slice` from the rest
+///
+/// a value that runs past the end of the slice is returned empty.
+fn split_item<'r>(slice: &mut &'r [BucketNode<'r>], config: BucketConfig) -> BucketRef<'r> {
+    let len = value_len(slice, 0).unwrap_or(slice.len());
+    let (item, rest) = slice.split_at(len);
+    *slice = rest;
+    BucketRef { slice: item, config }
+}
+
+impl<'r> BucketRef<'r> {
+    /// the value at the front of `slice`
+    pub(crate) fn item(mut slice: &'r [BucketNode<'r>], config: BucketConfig) -> Self {
+        split_item(&mut slice, config)
+    }
+
+    /// the value, for matching on
+    pub fn value(&self) -> ValueRef<'r> {
+        let Some(node) = self.slice.first() else {
+            return ValueRef::Empty;
+        };
+        let child = || BucketRef::item(&self.slice[1..], self.config);
+        match node {
+            BucketNode::Consumed | BucketNode::Unsized => ValueRef::Empty,
+            BucketNode::Unit => ValueRef::Unit,
+            BucketNode::Bool(val) => ValueRef::Bool(*val),
+            BucketNode::Char(val) => ValueRef::Char(*val),
+            BucketNode::U8(val) => ValueRef::UInt(*val as u128),
+            BucketNode::U16(val) => ValueRef::UInt(*val as u128),
+            BucketNode::U32(val) => ValueRef::UInt(*val as u128),
+            BucketNode::U64(val) => ValueRef::UInt(*val as u128),
+            BucketNode::U128(val) => ValueRef::UInt(*val),
+            BucketNode::I8(val) => ValueRef::Int(*val as i128),
+            BucketNode::I16(val) => ValueRef::Int(*val as i128),
+            BucketNode::I32(val) => ValueRef::Int(*val as i128),
+            BucketNode::I64(val) => ValueRef::Int(*val as i128),
+            BucketNode::I128(val) => ValueRef::Int(*val),
+            BucketNode::F32(val) => ValueRef::Float(*val as f64),
+            BucketNode::F64(val) => ValueRef::Float(*val),
+            BucketNode::String(val) => ValueRef::String(val),
+            BucketNode::StringRef(val) => ValueRef::String(val),
+            BucketNode::Field(val) => ValueRef::String(val),
+            BucketNode::Bytes(val) => ValueRef::Bytes(val),
+            BucketNode::BytesRef(val) => ValueRef::Bytes(val),
+            BucketNode::None => ValueRef::None,
+            BucketNode::Some => ValueRef::Some(child()),
+            BucketNode::Seq(size, _) => ValueRef::Seq(SeqRef { slice: self.slice, size: *size, config: self.config }),
+            BucketNode::Map(size, _) => ValueRef::Map(MapRef { slice: self.slice, size: *size, config: self.config }),
+            BucketNode::NewType => ValueRef::NewType(child()),
+            BucketNode::UnitStruct(name) => ValueRef::UnitStruct(name),
+            BucketNode::NewTypeStruct(name) => ValueRef::NewTypeStruct(name, child()),
+            BucketNode::TupleStruct(name) => match child().as_seq() {
+                Some(seq) => ValueRef::TupleStruct(name, seq),
+                None => ValueRef::Empty,
+            },
+            BucketNode::Struct(name) => match child().as_map() {
+                Some(map) => ValueRef::Struct(name, map),
+                None => ValueRef::Empty,
+            },
+            BucketNode::Variant(name, index, variant, kind) => {
+                let payload = match kind {
+                    VariantKind::Unit => None,
+                    _ => Some(child()),
+                };
+                ValueRef::Variant(name, *index, variant, payload)
+            }
+            BucketNode::Enum => {
+                let mut rest = &self.slice[1..];
+                let variant = split_item(&mut rest, self.config);
+                let payload = split_item(&mut rest, self.config);
+                ValueRef::Enum(variant, payload)
+            }
+        }
+    }
+
+    /// the shape of the value
+    pub fn kind(&self) -> BucketKind {
+        let Some(node) = self.slice.first() else {
+            return BucketKind::Empty;
+        };
+        match node {
+            BucketNode::Consumed | BucketNode::Unsized => BucketKind::Empty,
+            BucketNode::Unit => BucketKind::Unit,
+            BucketNode::Bool(_) => BucketKind::Bool,
+            BucketNode::Char(_) => BucketKind::Char,
+            BucketNode::U8(_) | BucketNode::U16(_) | BucketNode::U32(_)
+            | BucketNode::U64(_) | BucketNode::U128(_) => BucketKind::UInt,
+            BucketNode::I8(_) | BucketNode::I16(_) | BucketNode::I32(_)
+            | BucketNode::I64(_) | BucketNode::I128(_) => BucketKind::Int,
+            BucketNode::F32(_) | BucketNode::F64(_) => BucketKind::Float,
+            BucketNode::String(_) | BucketNode::StringRef(_) | BucketNode::Field(_) => BucketKind::String,
+            BucketNode::Bytes(_) | BucketNode::BytesRef(_) => BucketKind::Bytes,
+            BucketNode::None => BucketKind::None,
+            BucketNode::Some => BucketKind::Some,
+            BucketNode::Seq(..) => BucketKind::Seq,
+            BucketNode::Map(..) => BucketKind::Map,
+            BucketNode::NewType => BucketKind::NewType,
+            BucketNode::UnitStruct(_) => BucketKind::UnitStruct,
+            BucketNode::NewTypeStruct(_) => BucketKind::NewTypeStruct,
+            BucketNode::TupleStruct(_) => BucketKind::TupleStruct,
+            BucketNode::Struct(_) => BucketKind::Struct,
+            BucketNode::Variant(..) => BucketKind::Variant,
+            BucketNode::Enum => BucketKind::Enum,
+        }
+    }
+
+    /// the value as a string (or struct field name)
+    pub fn as_str(&self) -> Option<&'r str> {
+        self.slice.first()?.as_str()
+    }
+
+    /// the value as a `u64`, if it's an integer that fits
+    pub fn as_u64(&self) -> Option<u64> {
+        match self.value() {
+            ValueRef::UInt(val) => u64::try_from(val).ok(),
+            ValueRef::Int(val) => u64::try_from(val).ok(),
+            _ => None,
+        }
+    }
+
+    /// the value as an `i64`, if it's an integer that fits
+    pub fn as_i64(&self) -> Option<i64> {
+        match self.value() {
+            ValueRef::UInt(val) => i64::try_from(val).ok(),
+            ValueRef::Int(val) => i64::try_from(val).ok(),
+            _ => None,
+        }
+    }
+
+    /// the value as an `f64`, if it's a float or integer (which may lose precision)
+    pub fn as_f64(&self) -> Option<f64> {
+        match self.value() {
+            ValueRef::Float(val) => Some(val),
+            ValueRef::UInt(val) => Some(val as f64),
+            ValueRef::Int(val) => Some(val as f64),
+            _ => None,
+        }
+    }
+
+    /// the value as a `bool`
+    pub fn as_bool(&self) -> Option<bool> {
+        match self.slice.first()? {
+            BucketNode::Bool(val) => Some(*val),
+            _ => None,
+        }
+    }
+
+    /// the value as bytes
+    pub fn as_bytes(&self) -> Option<&'r [u8]> {
+        match self.slice.first()? {
+            BucketNode::Bytes(val) => Some(val),
+            BucketNode::BytesRef(val) => Some(val),
+            _ => None,
+        }
+    }
+
+    /// if the value is `None` or unit
+    pub fn is_null(&self) -> bool {
+        matches!(self.slice.first(), Some(BucketNode::None | BucketNode::Unit))
+    }
+
+    /// the number of elements of a sequence or entries of a map (including tuple structs/structs)
+    ///
+    /// Returns `None` for any other value.
+    pub fn len(&self) -> Option<usize> {
+        match self.slice.get(container(self.slice))? {
+            BucketNode::Seq(size, _) | BucketNode::Map(size, _) => Some(*size),
+            _ => None,
+        }
+    }
+
+    /// if a sequence or map (including tuple structs/structs) has no elements
+    ///
+    /// Returns `None` for any other value.
+    pub fn is_empty(&self) -> Option<bool> {
+        self.len().map(|len| len == 0)
+    }
+
+    /// the value as a sequence (including tuple structs)
+    pub fn as_seq(&self) -> Option<SeqRef<'r>> {
+        let slice = &self.slice[container(self.slice)..];
+        let BucketNode::Seq(size, _) = slice.first()? else {
+            return None;
+        };
+        Some(SeqRef { slice, size: *size, config: self.config })
+    }
+
+    /// the value as a map (including structs)
+    pub fn as_map(&self) -> Option<MapRef<'r>> {
+        let slice = &self.slice[container(self.slice)..];
+        let BucketNode::Map(size, _) = slice.first()? else {
+            return None;
+        };
+        Some(MapRef { slice, size: *size, config: self.config })
+    }
+
+    /// get the element at `index` of a sequence
+    ///
+    /// Returns `None` if this isn't a sequence or the index is out-of-bounds.
+    pub fn seq_index(&self, index: usize) -> Option<BucketRef<'r>> {
+        self.as_seq()?.get(index)
+    }
+
+    /// get the value of a map for a given key
+    ///
+    /// Only string keys are matched. Returns `None` if this isn't a map or the key is missing.
+    pub fn map_get(&self, key: &str) -> Option<BucketRef<'r>> {
+        self.as_map()?.get(key)
+    }
+
+    /// get the value at a path (see [`BucketPath`] for the syntax)
+    ///
+    /// `Some` and newtype wrappers are looked through, keys also match struct fields
+    /// and enum variants (stepping into their payload), and indexes also match integer map keys.
+    pub fn get(&self, path: &str) -> Result<BucketRef<'r>, PathError> {
+        self.get_path(&BucketPath::parse(path)?)
+    }
+
+    /// get the value at a parsed path
+    pub fn get_path(&self, path: &BucketPath) -> Result<BucketRef<'r>, PathError> {
+        let mut value = *self;
+        for (i, segment) in path.segments.iter().enumerate() {
+            value = value.step(segment).ok_or_else(|| PathError::Missing(BucketPath {
+                segments: path.segments[..=i].to_vec(),
+            }))?;
+        }
+        Ok(value)
+    }
+
+    /// deserialize the value at a path into the given type (clone method)
+    ///
+    /// A missing path (or invalid syntax) is returned as a custom error of `E`.
+    /// When paths are tracked, errors include the path looked up.
+    /// - `T` as `Deserialize`
+    /// - `E` as a `serde::de::Error`
+    pub fn get_into<T: Deserialize<'r>, E: serde::de::Error>(&self, path: &str) -> Result<T, E> {
+        let path = BucketPath::parse(path).map_err(|error| Fault::Path(error).error(None))?;
+        let value = self.get_path(&path).map_err(|error| Fault::Path(error).error(None))?;
+        let mut deserializer = BucketDeserializer::new(BucketBuffer::Ref(value.slice), value.config);
+        if value.config.track_path {
+            deserializer.path = path.segments;
+        }
+        let result = T::deserialize(&mut deserializer);
+        deserializer.finish(result)
+    }
+
+    /// look through `Some` and newtype wrappers
+    fn unwrapped(&self) -> BucketRef<'r> {
+        let mut value = *self;
+        while let Some(BucketNode::Some | BucketNode::NewType | BucketNode::NewTypeStruct(_)) = value.slice.first() {
+            value = BucketRef::item(&value.slice[1..], value.config);
+        }
+        value
+    }
+
+    /// resolve a single path segment
+    fn step(&self, segment: &PathSegment) -> Option<BucketRef<'r>> {
+        let value = self.unwrapped();
+        match (value.value(), segment) {
+            (ValueRef::Seq(seq) | ValueRef::TupleStruct(_, seq), PathSegment::Index(index)) => seq.get(*index),
+            (ValueRef::Seq(seq) | ValueRef::TupleStruct(_, seq), PathSegment::Key(key)) => seq.get(parse_index(key)?),
+            (ValueRef::Map(map) | ValueRef::Struct(_, map), PathSegment::Key(key)) => map.get(key)
+                // pointer tokens are always keys, so may name an integer key
+                .or_else(|| value.step(&PathSegment::Index(parse_index(key)?))),
+            (ValueRef::Map(map), PathSegment::Index(index)) => map.iter()
+                .find(|(k, _)| k.as_u64() == u64::try_from(*index).ok())
+                .map(|(_, value)| value),
+            (ValueRef::Variant(_, _, variant, payload), PathSegment::Key(key)) if variant == key => payload,
+            (ValueRef::Enum(variant, payload), PathSegment::Key(key)) if variant.as_str() == Some(key) => Some(payload),
+            _ => None,
+        }
+    }
+
+    /// compare with another value, ignoring the order of map entries (see [`Bucket::eq_unordered`](crate::Bucket::eq_unordered))
+    pub fn eq_unordered(&self, other: &BucketRef<'_>) -> bool {
+        eq_unordered(self.slice, other.slice)
+    }
+
+    /// compare with another value, ignoring the width of numbers (see [`Bucket::eq_numeric`](crate::Bucket::eq_numeric))
+    pub fn eq_numeric(&self, other: &BucketRef<'_>) -> bool {
+        eq_numeric(self.slice, other.slice)
+    }
+
+    /// deserialize into (clone method)
+    ///
+    /// This function deserializes into the given type, borrowing owned data (Vec, String)
+    /// from the bucket - types that need ownership will clone it.
+    /// - `T` as `Deserialize`
+    /// - `E` as a `serde::de::Error`
+    pub fn deserialize_into_clone<T: Deserialize<'r>, E: serde::de::Error>(&self) -> Result<T, E> {
+        T::deserialize(&mut BucketDeserializer::new(BucketBuffer::Ref(self.slice), self.config))
+    }
+}
+
+/// A read-only view of a sequence in a `Bucket`
+#[derive(Copy, Clone)]
+pub struct SeqRef<'r> {
+    slice: &'r [BucketNode<'r>],
+    size: usize,
+    config: BucketConfig,
+}
+
+impl<'r> SeqRef<'r> {
+    /// the number of elements
+    pub fn len(&self) -> usize {
+        self.size
+    }
+
+    /// if there are no elements
+    pub fn is_empty(&self) -> bool {
+        self.size == 0
+    }
+
+    /// get the element at `index`
+    pub fn get(&self, index: usize) -> Option<BucketRef<'r>> {
+        self.iter().nth(index)
+    }
+
+    /// iterate over the elements
+    pub fn iter(&self) -> SeqRefIter<'r> {
+        SeqRefIter {
+            rest: &self.slice[1..],
+            size: self.size,
+            config: self.config,
+        }
+    }
+}
+
+impl<'r> Debug for SeqRef<'r> {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        debug_nodes(self.slice, f)
+    }
+}
+
+impl<'r> IntoIterator for SeqRef<'r> {
+    type Item = BucketRef<'r>;
+    type IntoIter = SeqRefIter<'r>;
+
+    fn into_iter(self) -> Self::IntoIter {
+        self.iter()
+    }
+}
+
+/// A read-only view of a map in a `Bucket`
+#[derive(Copy, Clone)]
+pub struct MapRef<'r> {
+    slice: &'r [BucketNode<'r>],
+    size: usize,
+    config: BucketConfig,
+}
+
+impl<'r> MapRef<'r> {
+    /// the number of entries
+    pub fn len(&self) -> usize {
+        self.size
+    }
+
+    /// if there are no entries
+    pub fn is_empty(&self) -> bool {
+        self.size == 0
+    }
+
+    /// get the value for a given key
+    ///
+    /// Only string keys are matched.
+    pub fn get(&self, key: &str) -> Option<BucketRef<'r>> {
+        self.iter()
+            .find(|(k, _)| k.as_str() == Some(key))
+            .map(|(_, value)| value)
+    }
+
+    /// iterate over the entries (key and value)
+    pub fn iter(&self) -> MapRefIter<'r> {
+        MapRefIter {
+            rest: &self.slice[1..],
+            size: self.size,
+            config: self.config,
+        }
+    }
+}
+
+impl<'r> Debug for MapRef<'r> {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        debug_nodes(self.slice, f)
+    }
+}
+
+impl<'r> IntoIterator for MapRef<'r> {
+    type Item = (BucketRef<'r>, BucketRef<'r>);
+    type IntoIter = MapRefIter<'r>;
+
+    fn into_iter(self) -> Self::IntoIter {
+        self.iter()
+    }
+}
+
+/// An iterator over the elements of a [`SeqRef`]
+pub struct SeqRefIter<'r> {
+    rest: &'r [BucketNode<'r>],
+    size: usize,
+    config: BucketConfig,
+}
+
+impl<'r> Iterator for SeqRefIter<'r> {
+    type Item = BucketRef<'r>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        if self.size == 0 || self.rest.is_empty() {
+            return None;
+        }
+        self.size -= 1;
+        Some(split_item(&mut self.rest, self.config))
+    }
+}
+
+/// An iterator over the entries of a [`MapRef`]
+pub struct MapRefIter<'r> {
+    rest: &'r [BucketNode<'r>],
+    size: usize,
+    config: BucketConfig,
+}
+
+impl<'r> Iterator for MapRefIter<'r> {
+    type Item = (BucketRef<'r>, BucketRef<'r>);
+
+    fn next(&mut self) -> Option<Self::Item> {
+        if self.size == 0 || self.rest.is_empty() {
+            return None;
+        }
+        self.size -= 1;
+        let key = split_item(&mut self.rest, self.config);
+        let value = split_item(&mut self.rest, self.config);
+        Some((key, value))
+    }
+}
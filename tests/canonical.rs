@@ -0,0 +1,76 @@
+use std::collections::HashSet;
+use serde::Serialize;
+use serde_bucket::Bucket;
+
+mod common;
+use common::captured;
+
+fn bucket(input: &str) -> Bucket<'_> {
+    serde_json::from_str(input).unwrap()
+}
+
+const A: &str = r#"{"b": {"y": [1, {"q": 1, "p": 2}], "x": null}, "a": "z\n", "1": true}"#;
+const B: &str = r#"{"1": true, "a": "z\n", "b": {"x": null, "y": [1, {"p": 2, "q": 1}]}}"#;
+
+#[test]
+fn unordered() {
+    assert_ne!(bucket(A), bucket(B));
+    assert!(bucket(A).eq_unordered(&bucket(B)));
+    assert!(bucket(A).get("b").unwrap().eq_unordered(&bucket(B).get("b").unwrap()));
+    // arrays keep their order
+    assert!(!bucket("[1, 2]").eq_unordered(&bucket("[2, 1]")));
+    assert!(!bucket(A).eq_unordered(&bucket(r#"{"1": true, "a": "z\n", "b": {"x": null, "y": [1, {"p": 1, "q": 2}]}}"#)));
+}
+
+#[test]
+fn canonicalize() {
+    let mut a = bucket(A);
+    a.canonicalize();
+    assert_eq!(serde_json::to_string(&a).unwrap(), r#"{"1":true,"a":"z\n","b":{"x":null,"y":[1,{"p":2,"q":1}]}}"#);
+
+    let set: HashSet<Bucket> = [A, B].into_iter().map(|input| {
+        let mut bucket = bucket(input);
+        bucket.canonicalize();
+        bucket
+    }).collect();
+    assert_eq!(set.len(), 1);
+}
+
+#[derive(Serialize)]
+struct Server {
+    name: &'static str,
+    ports: Vec<u64>,
+    tls: Tls,
+    origin: Origin,
+}
+
+#[derive(Serialize)]
+struct Tls {
+    on: bool,
+}
+
+#[derive(Serialize)]
+struct Origin(i64, u64);
+
+#[test]
+fn captured_structs() {
+    let server = captured(&Server { name: "a", ports: vec![80], tls: Tls { on: true }, origin: Origin(-1, 1) });
+    let json = bucket(r#"{"tls": {"on": true}, "origin": [-1, 1], "ports": [80], "name": "a"}"#);
+    assert_ne!(server, json);
+    // structs are compared as the maps they serialize as
+    assert!(server.eq_unordered(&json));
+    assert!(json.eq_unordered(&server));
+    assert!(server.view().get("tls").unwrap().eq_unordered(&json.get("tls").unwrap()));
+    assert!(!server.eq_unordered(&bucket(r#"{"tls": {"on": false}, "origin": [-1, 1], "ports": [80], "name": "a"}"#)));
+    assert!(!server.eq_unordered(&bucket(r#"{"tls": {"on": true}, "origin": [-1, 1], "ports": [80], "name": "a", "x": 1}"#)));
+    // but numbers still need the same width
+    assert!(!captured(&Tls { on: true }).eq_unordered(&bucket(r#"{"on": 1}"#)));
+    assert!(!captured(&Origin(-1, 1)).eq_unordered(&bucket("[-1.0, 1.0]")));
+
+    // struct fields sort by name, so canonical structs serialize like canonical maps
+    let mut server = server;
+    let mut json = json;
+    server.canonicalize();
+    json.canonicalize();
+    assert_eq!(serde_json::to_string(&server).unwrap(), serde_json::to_string(&json).unwrap());
+}
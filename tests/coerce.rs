@@ -0,0 +1,30 @@
+use serde::Deserialize;
+use serde_bucket::Bucket;
+
+#[derive(Debug, PartialEq, Deserialize)]
+struct Query {
+    page: u32,
+    verbose: bool,
+    scale: f64,
+    sep: char,
+}
+
+#[test]
+fn strings() {
+    let mut bucket: Bucket = serde_json::from_str(r#"{"page": "42", "verbose": "1", "scale": "0.5", "sep": ","}"#).unwrap();
+    assert!(bucket.deserialize_into_clone::<Query, serde_json::Error>().is_err());
+    bucket.set_coerce(true);
+    let query = bucket.deserialize_into_clone::<Query, serde_json::Error>().unwrap();
+    assert_eq!(query, Query { page: 42, verbose: true, scale: 0.5, sep: ',' });
+}
+
+#[test]
+fn numbers() {
+    let mut bucket: Bucket = serde_json::from_str("[1, 300, \"x\"]").unwrap();
+    bucket.set_coerce(true);
+    assert_eq!(bucket.deserialize_into_clone::<(f32, u16, String), serde_json::Error>().unwrap(), (1.0, 300, "x".to_owned()));
+    let error = bucket.deserialize_into_clone::<(u8, u8, String), serde_json::Error>().unwrap_err();
+    assert_eq!(error.to_string(), "invalid value: integer `300`, expected u8");
+    let error = bucket.deserialize_into_clone::<(u8, u16, u8), serde_json::Error>().unwrap_err();
+    assert_eq!(error.to_string(), "invalid value: string \"x\", expected u8");
+}
@@ -0,0 +1,7 @@
+use serde::Serialize;
+use serde_bucket::{to_bucket, Bucket};
+
+/// capture a value with it's types - so structs have their names, and `Field` keys
+pub fn captured<T: Serialize>(value: &T) -> Bucket<'static> {
+    to_bucket::<_, serde_json::Error>(value).unwrap()
+}
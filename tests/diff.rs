@@ -0,0 +1,82 @@
+use serde::Serialize;
+use serde_bucket::{diff, diff_values, Bucket, BucketChange};
+
+mod common;
+use common::captured;
+
+fn bucket(input: &str) -> Bucket<'_> {
+    serde_json::from_str(input).unwrap()
+}
+
+#[test]
+fn changes() {
+    let a = bucket(r#"{"name": "a", "ports": [80, 443], "tls": {"on": true}, "old": 1}"#);
+    let b = bucket(r#"{"tls": {"on": false}, "name": "a", "ports": [80], "new": [1]}"#);
+    assert!(diff(&a, &a).is_empty());
+    let changes = diff(&a, &b);
+    let paths: Vec<String> = changes.iter().map(|change| change.path().to_string()).collect();
+    assert_eq!(paths, ["ports[1]", "tls.on", "old", "new"]);
+    assert!(matches!(&changes.changes()[0], BucketChange::Removed { value, .. } if value.view().as_u64() == Some(443)));
+    assert_eq!(
+        serde_json::to_string(&changes).unwrap(),
+        r#"[{"kind":"removed","path":"ports[1]","value":443},{"kind":"changed","path":"tls.on","old":true,"new":false},{"kind":"removed","path":"old","value":1},{"kind":"added","path":"new","value":[1]}]"#,
+    );
+}
+
+#[derive(Serialize)]
+struct Config {
+    name: &'static str,
+    retries: Option<u8>,
+}
+
+#[test]
+fn values() {
+    let diff = diff_values::<_, _, serde_json::Error>(
+        &Config { name: "a", retries: Some(1) },
+        &Config { name: "b", retries: None },
+    ).unwrap();
+    assert_eq!(
+        serde_json::to_string(&diff).unwrap(),
+        r#"[{"kind":"changed","path":"name","old":"a","new":"b"},{"kind":"changed","path":"retries","old":1,"new":null}]"#,
+    );
+}
+
+#[derive(Serialize)]
+struct Server {
+    name: &'static str,
+    port: Port,
+    tls: Tls,
+}
+
+#[derive(Serialize)]
+struct Port(u64);
+
+#[derive(Serialize)]
+struct Tls {
+    on: bool,
+}
+
+#[derive(Serialize)]
+struct Other {
+    on: bool,
+}
+
+#[test]
+fn captured_structs() {
+    let server = captured(&Server { name: "a", port: Port(80), tls: Tls { on: true } });
+    // structs are compared with the maps they serialize as
+    assert!(diff(&server, &bucket(r#"{"tls": {"on": true}, "port": 80, "name": "a"}"#)).is_empty());
+    let json = bucket(r#"{"name": "a", "port": 443, "tls": {"on": true, "v": 3}, "extra": null}"#);
+    let paths: Vec<String> = diff(&server, &json).iter().map(|change| change.path().to_string()).collect();
+    assert_eq!(paths, ["port", "tls.v", "extra"]);
+    let changes = diff(&json, &server);
+    assert_eq!(
+        serde_json::to_string(&changes).unwrap(),
+        r#"[{"kind":"changed","path":"port","old":443,"new":80},{"kind":"removed","path":"tls.v","value":3},{"kind":"removed","path":"extra","value":null}]"#,
+    );
+
+    // but structs of different names are changed as a whole
+    let (tls, other) = (captured(&Tls { on: true }), captured(&Other { on: true }));
+    let changes = diff(&tls, &other);
+    assert!(matches!(changes.changes(), [BucketChange::Changed { path, .. }] if path.segments().is_empty()));
+}
@@ -0,0 +1,161 @@
+use serde::{Deserialize, Serialize};
+use serde::de::value::{EnumAccessDeserializer, MapAccessDeserializer, MapDeserializer, StrDeserializer};
+use serde_bucket::{to_bucket, Bucket};
+use serde_json::json;
+
+#[derive(Debug, PartialEq, Serialize, Deserialize)]
+enum Shape {
+    Empty,
+    Circle(f64),
+    Line(i32, i32),
+    Rect { w: u8, h: u8 },
+}
+
+fn shapes() -> Vec<Shape> {
+    vec![Shape::Empty, Shape::Circle(1.5), Shape::Line(-1, 1), Shape::Rect { w: 2, h: 3 }]
+}
+
+#[test]
+fn from_json() {
+    let input = r#"["Empty", {"Circle": 1.5}, {"Line": [-1, 1]}, {"Rect": {"w": 2, "h": 3}}]"#;
+    let mut bucket: Bucket = serde_json::from_str(input).unwrap();
+    assert_eq!(bucket.deserialize_into_clone::<Vec<Shape>, serde_json::Error>().unwrap(), shapes());
+    assert_eq!(bucket.deserialize_into::<Vec<Shape>, serde_json::Error>().unwrap(), shapes());
+}
+
+#[test]
+fn from_to_bucket() {
+    for shape in shapes() {
+        let mut bucket: Bucket = to_bucket::<_, serde_json::Error>(&shape).unwrap();
+        assert_eq!(bucket.deserialize_into_clone::<Shape, serde_json::Error>().unwrap(), shape);
+        assert_eq!(bucket.deserialize_into::<Shape, serde_json::Error>().unwrap(), shape);
+    }
+}
+
+#[test]
+fn invalid() {
+    let bucket: Bucket = serde_json::from_str(r#""Square""#).unwrap();
+    let error = bucket.deserialize_into_clone::<Shape, serde_json::Error>().unwrap_err();
+    assert_eq!(error.to_string(), "unknown variant `Square`, expected one of `Empty`, `Circle`, `Line`, `Rect`");
+    let bucket: Bucket = serde_json::from_str(r#"{"Circle": 1, "Line": [1, 2]}"#).unwrap();
+    assert!(bucket.deserialize_into_clone::<Shape, serde_json::Error>().is_err());
+    let bucket: Bucket = serde_json::from_str(r#""Circle""#).unwrap();
+    assert!(bucket.deserialize_into_clone::<Shape, serde_json::Error>().is_err());
+}
+
+#[derive(Debug, PartialEq, Serialize, Deserialize)]
+struct Meters(u32);
+
+#[test]
+fn captured_as_json() {
+    for shape in shapes() {
+        let bucket: Bucket = to_bucket::<_, serde_json::Error>(&shape).unwrap();
+        let value = bucket.deserialize_into_clone::<serde_json::Value, serde_json::Error>().unwrap();
+        assert_eq!(value, serde_json::to_value(&shape).unwrap());
+    }
+    let bucket: Bucket = to_bucket::<_, serde_json::Error>(&Meters(5)).unwrap();
+    assert_eq!(bucket.deserialize_into_clone::<serde_json::Value, serde_json::Error>().unwrap(), json!(5));
+    assert_eq!(bucket.deserialize_into_clone::<Meters, serde_json::Error>().unwrap(), Meters(5));
+}
+
+#[derive(Debug, PartialEq, Serialize, Deserialize)]
+struct Flattened {
+    id: u8,
+    #[serde(flatten)]
+    inner: Inner,
+}
+
+#[derive(Debug, PartialEq, Serialize, Deserialize)]
+struct Inner {
+    shape: Shape,
+    length: Meters,
+}
+
+#[derive(Debug, PartialEq, Serialize, Deserialize)]
+#[serde(untagged)]
+enum Untagged {
+    Number(u8),
+    Shape(Shape),
+}
+
+#[test]
+fn flatten_and_untagged() {
+    for shape in shapes() {
+        let value = Flattened { id: 1, inner: Inner { shape, length: Meters(2) } };
+        let mut bucket: Bucket = to_bucket::<_, serde_json::Error>(&value).unwrap();
+        assert_eq!(bucket.deserialize_into::<Flattened, serde_json::Error>().unwrap(), value);
+    }
+    for shape in shapes() {
+        let mut bucket: Bucket = to_bucket::<_, serde_json::Error>(&shape).unwrap();
+        assert_eq!(bucket.deserialize_into::<Untagged, serde_json::Error>().unwrap(), Untagged::Shape(shape));
+    }
+}
+
+#[test]
+fn enum_access() {
+    // formats without a self-describing enum representation (like RON) hand over enums with `visit_enum`
+    let deserializer = EnumAccessDeserializer::new(StrDeserializer::<serde_json::Error>::new("Empty"));
+    let mut bucket = Bucket::deserialize(deserializer).unwrap();
+    assert_eq!(serde_json::to_string(&bucket).unwrap(), r#""Empty""#);
+    assert_eq!(bucket.deserialize_into::<Shape, serde_json::Error>().unwrap(), Shape::Empty);
+
+    let variants = [("Circle", json!(1.5)), ("Line", json!([-1, 1])), ("Rect", json!({"w": 2, "h": 3}))];
+    for ((variant, payload), shape) in variants.into_iter().zip(shapes().into_iter().skip(1)) {
+        let map = MapDeserializer::<_, serde_json::Error>::new([(variant, payload.clone())].into_iter());
+        let mut bucket = Bucket::deserialize(EnumAccessDeserializer::new(MapAccessDeserializer::new(map))).unwrap();
+        assert_eq!(serde_json::to_value(&bucket).unwrap(), json!({variant: payload}));
+        assert_eq!(bucket.deserialize_into::<Shape, serde_json::Error>().unwrap(), shape);
+    }
+}
+
+/// an enum whose payload fails to read, as corrupt input would
+struct Corrupt;
+
+impl<'de> serde::Deserializer<'de> for Corrupt {
+    type Error = serde_json::Error;
+
+    fn deserialize_any<V: serde::de::Visitor<'de>>(self, visitor: V) -> Result<V::Value, Self::Error> {
+        visitor.visit_enum(self)
+    }
+
+    serde::forward_to_deserialize_any! {
+        bool i8 i16 i32 i64 i128 u8 u16 u32 u64 u128 f32 f64 char str string bytes byte_buf option unit
+        unit_struct newtype_struct seq tuple tuple_struct map struct enum identifier ignored_any
+    }
+}
+
+impl<'de> serde::de::EnumAccess<'de> for Corrupt {
+    type Error = serde_json::Error;
+    type Variant = Self;
+
+    fn variant_seed<V: serde::de::DeserializeSeed<'de>>(self, seed: V) -> Result<(V::Value, Self), Self::Error> {
+        Ok((seed.deserialize(StrDeserializer::new("Circle"))?, self))
+    }
+}
+
+impl<'de> serde::de::VariantAccess<'de> for Corrupt {
+    type Error = serde_json::Error;
+
+    fn unit_variant(self) -> Result<(), Self::Error> {
+        Err(serde::de::Error::custom("corrupt payload"))
+    }
+
+    fn newtype_variant_seed<T: serde::de::DeserializeSeed<'de>>(self, _seed: T) -> Result<T::Value, Self::Error> {
+        Err(serde::de::Error::custom("corrupt payload"))
+    }
+
+    fn tuple_variant<V: serde::de::Visitor<'de>>(self, _len: usize, _visitor: V) -> Result<V::Value, Self::Error> {
+        Err(serde::de::Error::custom("corrupt payload"))
+    }
+
+    fn struct_variant<V: serde::de::Visitor<'de>>(self, _fields: &'static [&'static str], _visitor: V) -> Result<V::Value, Self::Error> {
+        Err(serde::de::Error::custom("corrupt payload"))
+    }
+}
+
+#[test]
+fn corrupt_payload() {
+    // only a unit variant is captured without a payload - other errors are returned
+    let error = Bucket::deserialize(Corrupt).unwrap_err();
+    assert_eq!(error.to_string(), "corrupt payload");
+}
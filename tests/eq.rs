@@ -0,0 +1,56 @@
+use std::collections::HashSet;
+use serde::de::IntoDeserializer;
+use serde::de::value::Error;
+use serde::{Deserialize, Serialize};
+use serde_bucket::Bucket;
+
+mod common;
+use common::captured;
+
+fn bucket(input: &str) -> Bucket<'_> {
+    serde_json::from_str(input).unwrap()
+}
+
+#[test]
+fn structural() {
+    // the escaped string is owned, the other borrowed
+    assert_eq!(bucket(r#"{"a": ["x\ny"]}"#), bucket(r#"{"a": ["x\u000ay"]}"#));
+    assert_ne!(bucket(r#"{"a": 1}"#), bucket(r#"{"a": 1.0}"#));
+    assert_ne!(bucket("0.0"), bucket("-0.0"));
+
+    let set: HashSet<Bucket> = ["[1, 2]", "[1, 2]", r#"{"a": "b"}"#, r#"{"a": "b\n"}"#].into_iter().map(bucket).collect();
+    assert_eq!(set.len(), 3);
+}
+
+#[test]
+fn numeric() {
+    let narrow = Bucket::deserialize(IntoDeserializer::<Error>::into_deserializer(1u8)).unwrap();
+    let wide = bucket("1");
+    assert_ne!(narrow, wide);
+    assert!(narrow.eq_numeric(&wide));
+    assert!(bucket("[-1, 2.5]").eq_numeric(&bucket("[-1, 2.5]")));
+    assert!(!bucket("[1]").eq_numeric(&bucket("[1.0]")));
+    assert!(bucket("0.0").eq_numeric(&bucket("-0.0")));
+    assert!(bucket(r#"{"a": [1]}"#).get("a[0]").unwrap().eq_numeric(&narrow.view()));
+}
+
+#[derive(Serialize)]
+struct Point {
+    x: u64,
+    y: u64,
+}
+
+#[test]
+fn captured_types() {
+    // a struct is captured with it's name and `Field` keys, so isn't equal to the map it serializes as
+    let point = captured(&Point { x: 1, y: 2 });
+    let json = bucket(r#"{"x": 1, "y": 2}"#);
+    assert_ne!(point, json);
+    assert!(!point.eq_numeric(&json));
+    assert_eq!(point, captured(&Point { x: 1, y: 2 }));
+    assert_ne!(point, captured(&Point { x: 1, y: 3 }));
+    assert_eq!(serde_json::to_string(&point).unwrap(), serde_json::to_string(&json).unwrap());
+
+    let set: HashSet<Bucket> = [captured(&Point { x: 1, y: 2 }), point, json].into_iter().collect();
+    assert_eq!(set.len(), 2);
+}
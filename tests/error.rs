@@ -0,0 +1,129 @@
+#![cfg(feature = "error")]
+
+use std::fmt::Formatter;
+use serde::{Deserialize, Deserializer};
+use serde::de::{MapAccess, Visitor};
+use serde_bucket::{Bucket, BucketError, BucketErrorKind, PathError, UnexpectedValue};
+
+#[derive(Debug, PartialEq, Deserialize)]
+struct Example {
+    a: u32,
+    b: Vec<u8>,
+}
+
+#[test]
+fn invalid_type_has_index() {
+    let bucket: Bucket = serde_json::from_str(r#"{"a": "x", "b": [1]}"#).unwrap();
+    let error = bucket.deserialize_into_clone::<Example, BucketError>().unwrap_err();
+    assert_eq!(error.kind(), &BucketErrorKind::InvalidType(UnexpectedValue::Str("x".to_owned()), "u32".to_owned()));
+    assert_eq!(error.index(), Some(2));
+    assert_eq!(error.to_string(), "invalid type: string \"x\", expected u32 at node 2");
+}
+
+#[test]
+fn consumed() {
+    let mut bucket: Bucket = serde_json::from_str(r#"["a\n", "b\n"]"#).unwrap();
+    bucket.deserialize_into::<Vec<String>, BucketError>().unwrap();
+    let error = bucket.deserialize_into::<Vec<String>, BucketError>().unwrap_err();
+    assert_eq!(error.kind(), &BucketErrorKind::Consumed);
+    assert_eq!(error.index(), Some(1));
+}
+
+#[test]
+fn missing_path() {
+    let bucket: Bucket = serde_json::from_str(r#"{"a": {"b": 1}}"#).unwrap();
+    let error = bucket.get_into::<u8, BucketError>("a.c").unwrap_err();
+    assert!(matches!(error.kind(), BucketErrorKind::Path(PathError::Missing(_))));
+    assert_eq!(error.path().unwrap().to_string(), "a.c");
+}
+
+#[test]
+fn tracked_path() {
+    let mut bucket: Bucket = serde_json::from_str(r#"{"a": 1, "b": [1, "x"]}"#).unwrap();
+    bucket.set_track_path(true);
+    let error = bucket.deserialize_into_clone::<Example, BucketError>().unwrap_err();
+    assert_eq!(error.kind(), &BucketErrorKind::InvalidType(UnexpectedValue::Str("x".to_owned()), "u8".to_owned()));
+    assert_eq!((error.index(), error.path().unwrap().to_string()), (Some(6), "b[1]".to_owned()));
+    assert_eq!(error.to_string(), "invalid type: string \"x\", expected u8 at b[1]");
+
+    let error = bucket.get_into::<u8, BucketError>("b").unwrap_err();
+    assert_eq!((error.index(), error.path().unwrap().to_string()), (Some(0), "b".to_owned()));
+    assert_eq!(error.to_string(), "invalid type: sequence, expected u8 at b");
+}
+
+#[test]
+fn missing_field_is_at_struct() {
+    let bucket: Bucket = serde_json::from_str(r#"[{"a": 1, "b": []}, {"a": 2}]"#).unwrap();
+    let error = bucket.deserialize_into_clone::<Vec<Example>, BucketError>().unwrap_err();
+    assert_eq!(error.kind(), &BucketErrorKind::MissingField("b"));
+    assert_eq!(error.index(), Some(6));
+}
+
+/// reads a map's value before it's key
+#[derive(Debug)]
+struct ValueFirst;
+
+impl<'de> Deserialize<'de> for ValueFirst {
+    fn deserialize<D>(deserializer: D) -> Result<Self, D::Error> where D: Deserializer<'de> {
+        deserializer.deserialize_map(ValueFirst)
+    }
+}
+
+impl<'de> Visitor<'de> for ValueFirst {
+    type Value = ValueFirst;
+
+    fn expecting(&self, formatter: &mut Formatter) -> std::fmt::Result {
+        formatter.write_str("a map")
+    }
+
+    fn visit_map<A>(self, mut map: A) -> Result<ValueFirst, A::Error> where A: MapAccess<'de> {
+        map.next_value::<u8>().map(|_| ValueFirst)
+    }
+}
+
+#[test]
+fn out_of_order() {
+    let bucket: Bucket = serde_json::from_str(r#"{"a": 1}"#).unwrap();
+    let error = bucket.deserialize_into_clone::<ValueFirst, BucketError>().unwrap_err();
+    assert_eq!(error.kind(), &BucketErrorKind::OutOfOrder("no key for this value"));
+    assert_eq!(error.index(), Some(1));
+    let error = bucket.deserialize_into_clone::<ValueFirst, serde_json::Error>().unwrap_err();
+    assert_eq!(error.to_string(), "out-of-order access - no key for this value at node 1");
+}
+
+#[test]
+fn serialize_consumed() {
+    let mut bucket: Bucket = serde_json::from_str(r#"["a\n"]"#).unwrap();
+    bucket.deserialize_into::<Vec<String>, BucketError>().unwrap();
+    let error = serde_json::to_string(&bucket).unwrap_err();
+    assert_eq!(error.to_string(), "value has already been consumed");
+}
+
+/// a string holding JSON, read into it's own bucket - keeping errors made while it's read
+#[derive(Debug)]
+struct Embedded {
+    stray: BucketError,
+    inner: Result<Vec<u32>, BucketError>,
+}
+
+impl<'de> Deserialize<'de> for Embedded {
+    fn deserialize<D>(deserializer: D) -> Result<Self, D::Error> where D: Deserializer<'de> {
+        let json = String::deserialize(deserializer)?;
+        let inner: Bucket = serde_json::from_str(&json).map_err(serde::de::Error::custom)?;
+        let stray = serde::de::Error::custom("stray");
+        Ok(Embedded { stray, inner: inner.deserialize_into_clone() })
+    }
+}
+
+#[test]
+fn unrelated_errors() {
+    // errors made while another bucket is being deserialized only have their own location
+    let bucket: Bucket = serde_json::from_str(r#"[1, "[1, \"x\"]"]"#).unwrap();
+    let (_, embedded) = bucket.deserialize_into_clone::<(u8, Embedded), BucketError>().unwrap();
+    assert_eq!(embedded.stray, BucketError::new(BucketErrorKind::Custom("stray".to_owned())));
+    let inner = embedded.inner.unwrap_err();
+    assert_eq!((inner.index(), inner.path()), (Some(2), None));
+
+    let error = bucket.deserialize_into_clone::<(u8, u8), BucketError>().unwrap_err();
+    assert_eq!(error.index(), Some(2));
+}
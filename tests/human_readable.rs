@@ -0,0 +1,31 @@
+#![cfg(feature = "serializer")]
+
+use std::net::IpAddr;
+use serde::Serialize;
+use serde_bucket::{to_bucket, Bucket};
+
+#[test]
+fn readable_by_default() {
+    let ip: IpAddr = "10.0.0.1".parse().unwrap();
+    let bucket = to_bucket::<_, serde_json::Error>(&ip).unwrap();
+    assert!(bucket.is_human_readable());
+    assert_eq!(bucket.view().as_str(), Some("10.0.0.1"));
+    assert_eq!(bucket.deserialize_into_clone::<IpAddr, serde_json::Error>().unwrap(), ip);
+}
+
+#[test]
+fn compact_round_trip() {
+    let ip: IpAddr = "10.0.0.1".parse().unwrap();
+    let mut bucket: Bucket = serde_json::from_str("null").unwrap();
+    bucket.set_human_readable(false);
+    ip.serialize(&mut bucket.serializer::<serde_json::Error>()).unwrap();
+    assert_eq!(bucket.view().as_str(), None);
+
+    // captured from a compact bucket, so it stays compact
+    let copy: Bucket = bucket.deserialize_into_clone::<_, serde_json::Error>().unwrap();
+    assert!(!copy.is_human_readable());
+    assert_eq!(copy.deserialize_into_clone::<IpAddr, serde_json::Error>().unwrap(), ip);
+
+    bucket.set_human_readable(true);
+    assert!(bucket.deserialize_into_clone::<IpAddr, serde_json::Error>().is_err());
+}
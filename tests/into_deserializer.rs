@@ -0,0 +1,50 @@
+use std::collections::HashMap;
+use serde::Deserialize;
+use serde::de::IntoDeserializer;
+use serde::de::value::{MapDeserializer, SeqDeserializer};
+use serde_bucket::{Bucket, OwnedBucket};
+
+#[derive(Debug, PartialEq, Deserialize)]
+struct Settings {
+    name: String,
+    ports: Vec<u16>,
+    mode: Mode,
+}
+
+#[derive(Debug, PartialEq, Deserialize)]
+enum Mode {
+    Fast,
+    Limit(u8),
+}
+
+fn bucket(input: &str) -> Bucket<'_> {
+    serde_json::from_str(input).unwrap()
+}
+
+#[test]
+fn by_value() {
+    let settings = bucket(r#"{"name": "a\nb", "ports": [80], "mode": {"Limit": 3}}"#);
+    let deserializer = IntoDeserializer::<serde_json::Error>::into_deserializer(settings);
+    assert_eq!(Settings::deserialize(deserializer).unwrap(), Settings { name: "a\nb".to_owned(), ports: vec![80], mode: Mode::Limit(3) });
+
+    let owned: OwnedBucket = serde_json::from_str(r#""Fast""#).unwrap();
+    let deserializer = IntoDeserializer::<serde_json::Error>::into_deserializer(owned);
+    assert_eq!(Mode::deserialize(deserializer).unwrap(), Mode::Fast);
+}
+
+#[test]
+fn value_deserializers() {
+    // buckets can be the values of serde's own deserializers, such as for settings collected from many sources
+    let entries = vec![("name", bucket(r#""x""#)), ("ports", bucket("[1, 2]")), ("mode", bucket(r#""Fast""#))];
+    let map = MapDeserializer::<_, serde_json::Error>::new(entries.into_iter());
+    assert_eq!(Settings::deserialize(map).unwrap(), Settings { name: "x".to_owned(), ports: vec![1, 2], mode: Mode::Fast });
+
+    let seq = SeqDeserializer::<_, serde_json::Error>::new(vec![bucket(r#"{"a": 1}"#), bucket("{}")].into_iter());
+    let maps = Vec::<HashMap<String, u8>>::deserialize(seq).unwrap();
+    assert_eq!(maps, [HashMap::from([("a".to_owned(), 1)]), HashMap::new()]);
+
+    let entries = vec![("ports", bucket(r#"["no"]"#))];
+    let map = MapDeserializer::<_, serde_json::Error>::new(entries.into_iter());
+    let error = Settings::deserialize(map).unwrap_err();
+    assert_eq!(error.to_string(), "invalid type: string \"no\", expected u16");
+}
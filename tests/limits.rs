@@ -0,0 +1,37 @@
+use serde::de::DeserializeSeed;
+use serde_bucket::{Bucket, BucketLimits, OwnedBucket};
+
+fn capture(input: &str, limits: BucketLimits) -> Result<Bucket<'_>, serde_json::Error> {
+    let mut bucket = Bucket::with_capacity(0);
+    bucket.seed().with_limits(limits).deserialize(&mut serde_json::Deserializer::from_str(input))?;
+    Ok(bucket)
+}
+
+#[test]
+fn within_limits() {
+    let limits = BucketLimits { max_depth: 2, max_nodes: 5, max_string_len: 3, max_total_bytes: 6 };
+    let bucket = capture(r#"{"abc": [1, "def"]}"#, limits).unwrap();
+    assert_eq!(bucket.get("abc[1]").unwrap().as_str(), Some("def"));
+}
+
+#[test]
+fn over_limits() {
+    let error = capture("[[[1]]]", BucketLimits { max_depth: 2, ..BucketLimits::default() }).unwrap_err();
+    assert!(error.to_string().starts_with("bucket limit exceeded - nested deeper than 2"));
+    let error = capture("[1, 2, 3]", BucketLimits { max_nodes: 3, ..BucketLimits::default() }).unwrap_err();
+    assert!(error.to_string().starts_with("bucket limit exceeded - more than 3 nodes"));
+    let error = capture(r#"["abcd"]"#, BucketLimits { max_string_len: 3, ..BucketLimits::default() }).unwrap_err();
+    assert!(error.to_string().starts_with("bucket limit exceeded - string of 4 bytes is longer than 3"));
+    let error = capture(r#"["ab", "cd"]"#, BucketLimits { max_total_bytes: 3, ..BucketLimits::default() }).unwrap_err();
+    assert!(error.to_string().starts_with("bucket limit exceeded - more than 3 bytes of strings"));
+}
+
+#[test]
+fn owned_limits() {
+    let mut bucket: OwnedBucket = serde_json::from_str("null").unwrap();
+    let limits = BucketLimits { max_depth: 2, ..BucketLimits::default() };
+    let error = bucket.seed().with_limits(limits).deserialize(&mut serde_json::Deserializer::from_str("[[[1]]]")).unwrap_err();
+    assert!(error.to_string().starts_with("bucket limit exceeded - nested deeper than 2"));
+    bucket.seed().with_limits(limits).deserialize(&mut serde_json::Deserializer::from_str("[[1]]")).unwrap();
+    assert_eq!(bucket.get("[0][0]").unwrap().as_u64(), Some(1));
+}
@@ -0,0 +1,76 @@
+use serde::{Deserialize, Serialize};
+use serde_bucket::{Bucket, BucketKind, SeqMerge};
+
+mod common;
+use common::captured;
+
+fn bucket(input: &str) -> Bucket<'_> {
+    serde_json::from_str(input).unwrap()
+}
+
+#[test]
+fn merge_patch() {
+    let mut doc = bucket(r#"{"title": "Goodbye!", "author": {"givenName": "John", "familyName": "Doe"}, "tags": ["example", "sample"], "content": "This will be unchanged"}"#);
+    doc.merge(&bucket(r#"{"title": "Hello!", "phoneNumber": "+01-123-456-7890", "author": {"familyName": null}, "tags": ["example"]}"#));
+    let expected = bucket(r#"{"title": "Hello!", "author": {"givenName": "John"}, "tags": ["example"], "content": "This will be unchanged", "phoneNumber": "+01-123-456-7890"}"#);
+    assert_eq!(doc, expected);
+    assert_eq!(doc.view().get("author").unwrap().len(), Some(1));
+
+    let mut doc = bucket(r#"[1, 2]"#);
+    doc.merge(&bucket(r#"{"a": {"b": null, "c": 1}}"#));
+    assert_eq!(doc, bucket(r#"{"a": {"c": 1}}"#));
+}
+
+#[test]
+fn seqs() {
+    let base = r#"{"ports": [80, {"host": "a", "tls": true}], "name": "x"}"#;
+    let other = bucket(r#"{"ports": [8080, {"tls": null}, 443]}"#);
+    let mut doc = bucket(base);
+    doc.merge_with(&other, SeqMerge::Append);
+    assert_eq!(doc, bucket(r#"{"ports": [80, {"host": "a", "tls": true}, 8080, {"tls": null}, 443], "name": "x"}"#));
+    let mut doc = bucket(base);
+    doc.merge_with(&other, SeqMerge::ByIndex);
+    assert_eq!(doc, bucket(r#"{"ports": [8080, {"host": "a"}, 443], "name": "x"}"#));
+}
+
+#[derive(Debug, PartialEq, Serialize, Deserialize)]
+struct Server {
+    name: String,
+    port: u64,
+    tls: Tls,
+    #[serde(default)]
+    tags: Vec<String>,
+}
+
+#[derive(Debug, PartialEq, Serialize, Deserialize)]
+struct Tls {
+    on: bool,
+    #[serde(default)]
+    version: Option<u8>,
+}
+
+#[test]
+fn captured_structs() {
+    let server = Server { name: "a".to_owned(), port: 80, tls: Tls { on: false, version: None }, tags: vec![] };
+    let mut doc = captured(&server);
+    doc.merge(&bucket(r#"{"port": 443, "tls": {"on": true, "version": 3}, "tags": ["x"]}"#));
+    let merged = Server { name: "a".to_owned(), port: 443, tls: Tls { on: true, version: Some(3) }, tags: vec!["x".to_owned()] };
+    assert_eq!(serde_json::to_string(&doc).unwrap(), serde_json::to_string(&merged).unwrap());
+    assert_eq!(doc.deserialize_into_clone::<Server, serde_json::Error>().unwrap(), merged);
+    // only fields were merged, so the structs keep their names
+    assert_eq!(doc.view().get("tls").unwrap().kind(), BucketKind::Struct);
+
+    // a key that isn't a field makes the struct a plain map
+    doc.merge(&bucket(r#"{"tls": {"extra": 1}, "name": "b", "port": null}"#));
+    assert_eq!(serde_json::to_string(&doc).unwrap(), r#"{"name":"b","tls":{"on":true,"version":3,"extra":1},"tags":["x"]}"#);
+    assert!(doc.eq_unordered(&bucket(r#"{"name": "b", "tls": {"on": true, "version": 3, "extra": 1}, "tags": ["x"]}"#)));
+    assert_eq!(doc.view().get("tls").unwrap().kind(), BucketKind::Map);
+    assert_eq!(doc.view().get("tls.on").unwrap().as_bool(), Some(true));
+
+    // and a captured struct patch merges into a plain map by field name
+    let mut doc = bucket(r#"{"name": "x", "port": 1, "tls": {"on": false, "v": 2}, "other": true}"#);
+    doc.merge(&captured(&Server { name: "c".to_owned(), port: 8, tls: Tls { on: true, version: None }, tags: vec![] }));
+    assert!(doc.eq_unordered(&bucket(r#"{"name": "c", "port": 8, "tls": {"on": true, "v": 2}, "other": true, "tags": []}"#)));
+    let server = doc.deserialize_into_clone::<Server, serde_json::Error>().unwrap();
+    assert_eq!(server, Server { name: "c".to_owned(), port: 8, tls: Tls { on: true, version: None }, tags: vec![] });
+}
@@ -0,0 +1,72 @@
+use serde::{Deserialize, Serialize};
+use serde_bucket::{to_bucket, Bucket};
+
+#[derive(Debug, PartialEq, Serialize, Deserialize)]
+struct Example {
+    a: Option<u8>,
+    b: Option<String>,
+    #[serde(default)]
+    c: Option<bool>,
+}
+
+#[test]
+fn json_null_is_none() {
+    let mut bucket: Bucket = serde_json::from_str("null").unwrap();
+    let value = bucket.deserialize_into::<Option<u8>, serde_json::Error>().unwrap();
+    assert_eq!(value, None);
+}
+
+#[test]
+fn json_value_is_some() {
+    let mut bucket: Bucket = serde_json::from_str("10").unwrap();
+    let value = bucket.deserialize_into::<Option<u8>, serde_json::Error>().unwrap();
+    assert_eq!(value, Some(10));
+}
+
+#[test]
+fn struct_fields() {
+    let mut bucket: Bucket = serde_json::from_str(r#"{"a": null, "b": "text"}"#).unwrap();
+    let value = bucket.deserialize_into::<Example, serde_json::Error>().unwrap();
+    assert_eq!(value, Example { a: None, b: Some("text".to_owned()), c: None });
+}
+
+#[test]
+fn struct_fields_clone() {
+    let bucket: Bucket = serde_json::from_str(r#"{"a": 1, "b": null, "c": false}"#).unwrap();
+    let value = bucket.deserialize_into_clone::<Example, serde_json::Error>().unwrap();
+    assert_eq!(value, Example { a: Some(1), b: None, c: Some(false) });
+}
+
+#[test]
+fn captured_some_and_none() {
+    for value in [None, Some(0u32), Some(u32::MAX)] {
+        let mut bucket: Bucket = to_bucket::<_, serde_json::Error>(&value).unwrap();
+        assert_eq!(bucket.deserialize_into::<Option<u32>, serde_json::Error>().unwrap(), value);
+    }
+}
+
+#[test]
+fn nested_options() {
+    for value in [None, Some(None), Some(Some(None)), Some(Some(Some(5u8)))] {
+        let mut bucket: Bucket = to_bucket::<_, serde_json::Error>(&value).unwrap();
+        assert_eq!(bucket.deserialize_into::<Option<Option<Option<u8>>>, serde_json::Error>().unwrap(), value);
+    }
+}
+
+#[test]
+fn options_in_sequences() {
+    let value = Some(vec![Some(1u8), None, Some(3)]);
+    let mut bucket: Bucket = to_bucket::<_, serde_json::Error>(&value).unwrap();
+    assert_eq!(bucket.deserialize_into::<Option<Vec<Option<u8>>>, serde_json::Error>().unwrap(), value);
+
+    let mut bucket: Bucket = serde_json::from_str("[1, null, 3]").unwrap();
+    let value = bucket.deserialize_into::<Vec<Option<u8>>, serde_json::Error>().unwrap();
+    assert_eq!(value, vec![Some(1), None, Some(3)]);
+}
+
+#[test]
+fn serialize_round_trip() {
+    let value = Example { a: Some(2), b: None, c: Some(true) };
+    let bucket: Bucket = to_bucket::<_, serde_json::Error>(&value).unwrap();
+    assert_eq!(serde_json::to_string(&bucket).unwrap(), serde_json::to_string(&value).unwrap());
+}
@@ -0,0 +1,39 @@
+use std::collections::VecDeque;
+use serde::Deserialize;
+use serde_bucket::{Bucket, OwnedBucket};
+
+#[derive(Debug, PartialEq, Deserialize)]
+struct Message {
+    text: String,
+    data: Vec<u8>,
+}
+
+fn read(input: &str) -> OwnedBucket {
+    serde_json::from_reader(input.as_bytes()).unwrap()
+}
+
+#[test]
+fn from_reader() {
+    let bucket = read(r#"{"text": "hello \"world\"", "data": [1, 2]}"#);
+    assert_eq!(serde_json::to_string(&bucket).unwrap(), r#"{"text":"hello \"world\"","data":[1,2]}"#);
+    assert_eq!(bucket.view().get("text").unwrap().as_str(), Some("hello \"world\""));
+    let message = bucket.deserialize_into::<Message, serde_json::Error>().unwrap();
+    assert_eq!(message, Message { text: "hello \"world\"".to_owned(), data: vec![1, 2] });
+
+    assert!(serde_json::from_reader::<_, OwnedBucket>(r#"{"text": "#.as_bytes()).is_err());
+}
+
+#[test]
+fn outlives_input() {
+    let mut queue = VecDeque::new();
+    for i in 0..3 {
+        let input = format!(r#"{{"text": "message {}", "data": [{}]}}"#, i, i);
+        let bucket: Bucket = serde_json::from_str(&input).unwrap();
+        queue.push_back(OwnedBucket::from(bucket));
+    }
+    queue.push_back(read(r#"{"text": "last", "data": []}"#));
+    let texts: Vec<String> = queue.into_iter()
+        .map(|bucket| bucket.deserialize_into::<Message, serde_json::Error>().unwrap().text)
+        .collect();
+    assert_eq!(texts, ["message 0", "message 1", "message 2", "last"]);
+}
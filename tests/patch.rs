@@ -0,0 +1,179 @@
+use serde::{Deserialize, Serialize};
+use serde_bucket::{Bucket, BucketPatch, BucketPath, PatchError, PathError};
+
+mod common;
+use common::captured;
+
+fn bucket(input: &str) -> Bucket<'_> {
+    serde_json::from_str(input).unwrap()
+}
+
+#[test]
+fn apply() {
+    let mut doc = bucket(r#"{"a": {"b": [1, 2]}, "c": "x"}"#);
+    let patch: BucketPatch = serde_json::from_str(r#"[
+        {"op": "add", "path": "/a/b/1", "value": {"d": 1}},
+        {"op": "add", "path": "/a/b/-", "value": 3},
+        {"op": "test", "path": "/a/b/1", "value": {"d": 1}},
+        {"op": "move", "from": "/c", "path": "/a/e"},
+        {"op": "copy", "from": "/a/b/0", "path": "/f"},
+        {"op": "replace", "path": "/a/b/2", "value": [4]},
+        {"op": "remove", "path": "/a/b/3"}
+    ]"#).unwrap();
+    doc.apply_patch(&patch).unwrap();
+    assert!(doc.eq_numeric(&bucket(r#"{"a": {"b": [1, {"d": 1}, [4]], "e": "x"}, "f": 1}"#)));
+
+    let failing: BucketPatch = serde_json::from_str(r#"[
+        {"op": "remove", "path": "/f"},
+        {"op": "test", "path": "/a/e", "value": "y"}
+    ]"#).unwrap();
+    let error = doc.apply_patch(&failing).unwrap_err();
+    assert!(matches!(error, PatchError::Test(1, _)));
+    assert_eq!(doc.view().get("f").unwrap().as_u64(), Some(1));
+    assert!(serde_json::from_str::<BucketPatch>(r#"[{"op": "add", "path": "/a"}]"#).is_err());
+}
+
+#[test]
+fn generate() {
+    const A: &str = r#"{"name": "a", "ports": [80, 443, 8080], "tls": {"on": true}, "old": 1}"#;
+    let a = bucket(A);
+    let b = bucket(r#"{"tls": {"on": false}, "name": "a", "ports": [80], "new": [1]}"#);
+    let patch = a.patch_to(&b);
+    assert_eq!(
+        serde_json::to_string(&patch).unwrap(),
+        r#"[{"op":"remove","path":"/ports/2"},{"op":"remove","path":"/ports/1"},{"op":"replace","path":"/tls/on","value":false},{"op":"remove","path":"/old"},{"op":"add","path":"/new","value":[1]}]"#,
+    );
+    let mut patched = bucket(A);
+    patched.apply_patch(&patch).unwrap();
+    assert!(patched.eq_unordered(&b));
+}
+
+#[derive(Debug, PartialEq, Serialize, Deserialize)]
+struct Server {
+    name: String,
+    port: u64,
+    tls: Option<Tls>,
+}
+
+#[derive(Debug, PartialEq, Serialize, Deserialize)]
+struct Tls {
+    on: bool,
+}
+
+fn patch(input: &str) -> BucketPatch<'static> {
+    serde_json::from_str(input).unwrap()
+}
+
+#[test]
+fn captured_structs() {
+    let server = Server { name: "a".to_owned(), port: 80, tls: Some(Tls { on: true }) };
+    let mut doc = captured(&server);
+    doc.apply_patch(&patch(r#"[
+        {"op": "test", "path": "/tls", "value": {"on": true}},
+        {"op": "test", "path": "", "value": {"tls": {"on": true}, "port": 80, "name": "a"}},
+        {"op": "replace", "path": "/port", "value": 443},
+        {"op": "add", "path": "/tls/on", "value": false}
+    ]"#)).unwrap();
+    // fields are replaced in place, so the structs keep their names
+    assert_eq!(doc, captured(&Server { name: "a".to_owned(), port: 443, tls: Some(Tls { on: false }) }));
+
+    // a key that isn't a field turns the struct into a map, with string keys
+    doc.apply_patch(&patch(r#"[{"op": "add", "path": "/tls/v", "value": 3}, {"op": "remove", "path": "/name"}, {"op": "add", "path": "/name", "value": "b"}]"#)).unwrap();
+    assert_eq!(serde_json::to_string(&doc).unwrap(), r#"{"port":443,"tls":{"on":false,"v":3},"name":"b"}"#);
+    doc.apply_patch(&patch(r#"[{"op": "test", "path": "", "value": {"name": "b", "port": 443, "tls": {"v": 3, "on": false}}}]"#)).unwrap();
+    assert_eq!(doc.view().get("tls.on").unwrap().as_bool(), Some(false));
+    let server = doc.deserialize_into_clone::<Server, serde_json::Error>().unwrap();
+    assert_eq!(server, Server { name: "b".to_owned(), port: 443, tls: Some(Tls { on: false }) });
+}
+
+#[test]
+fn generate_captured() {
+    let server = captured(&Server { name: "a".to_owned(), port: 80, tls: None });
+    let json = bucket(r#"{"name": "a", "port": 8080, "tls": {"on": true}, "x": [1]}"#);
+    let patch = server.patch_to(&json);
+    assert_eq!(
+        serde_json::to_string(&patch).unwrap(),
+        r#"[{"op":"replace","path":"/port","value":8080},{"op":"replace","path":"/tls","value":{"on":true}},{"op":"add","path":"/x","value":[1]}]"#,
+    );
+    let mut patched = captured(&Server { name: "a".to_owned(), port: 80, tls: None });
+    patched.apply_patch(&patch).unwrap();
+    assert!(patched.eq_unordered(&json));
+    // and back again
+    let patch = json.patch_to(&server);
+    let mut json = bucket(r#"{"name": "a", "port": 8080, "tls": {"on": true}, "x": [1]}"#);
+    json.apply_patch(&patch).unwrap();
+    assert!(json.eq_unordered(&server));
+}
+
+#[test]
+fn generate_round_trips() {
+    let cases = [
+        ("[1, 2, 3]", "[1]"),
+        ("[1]", "[1, 2, 3]"),
+        ("[[1, 2], {\"a\": [3, 4]}]", "[[1], {\"a\": [3, 4, 5]}, 6]"),
+        (r#"{"a/b": 1, "c~": {"d": [1, 2]}}"#, r#"{"a/b": 2, "c~": {"d": [2]}, "e": null}"#),
+        (r#"{"a": 1}"#, "[1]"),
+        ("null", r#"{"a": 1}"#),
+        (r#"{"a": {"b": {"c": 1}}}"#, r#"{"a": {"b": {}}, "b": 1}"#),
+    ];
+    for (a, b) in cases {
+        let (source, target) = (bucket(a), bucket(b));
+        let patch = source.patch_to(&target);
+        let mut doc = bucket(a);
+        doc.apply_patch(&patch).unwrap();
+        assert!(doc.eq_unordered(&target), "{a} -> {b}: {}", serde_json::to_string(&patch).unwrap());
+        assert!(source.patch_to(&bucket(a)).is_empty());
+    }
+}
+
+#[derive(Serialize)]
+struct W(u32);
+
+#[derive(Serialize)]
+struct Wrapped {
+    a: Option<u32>,
+    w: W,
+    o: Option<W>,
+    s: Vec<Option<u32>>,
+}
+
+#[test]
+fn generate_round_trips_captured() {
+    // wrappers that both values have are kept in the patch's values
+    let cases: [fn() -> (Bucket<'static>, Bucket<'static>); 6] = [
+        || (captured(&Wrapped { a: Some(1), w: W(1), o: Some(W(1)), s: vec![Some(1)] }), captured(&Wrapped { a: Some(2), w: W(2), o: Some(W(3)), s: vec![Some(2), None] })),
+        || (captured(&Wrapped { a: None, w: W(1), o: None, s: vec![None] }), captured(&Wrapped { a: Some(2), w: W(1), o: Some(W(2)), s: vec![Some(1)] })),
+        || (captured(&Wrapped { a: Some(1), w: W(1), o: Some(W(1)), s: vec![] }), captured(&Wrapped { a: None, w: W(1), o: None, s: vec![] })),
+        || (captured(&Some(5u32)), captured(&Some(6u32))),
+        || (captured(&W(5)), captured(&W(6))),
+        || (captured(&Some(W(5))), captured(&Some(W(6)))),
+    ];
+    for case in cases {
+        let (source, target) = case();
+        let patch = source.patch_to(&target);
+        let (mut doc, _) = case();
+        doc.apply_patch(&patch).unwrap();
+        assert_eq!(doc, target, "{}", serde_json::to_string(&patch).unwrap());
+    }
+}
+
+#[test]
+fn failures() {
+    let cases = [
+        (r#"[{"op": "remove", "path": ""}]"#, PatchError::Operation(0, "can't remove the root")),
+        (r#"[{"op": "move", "from": "/a", "path": "/a/b"}]"#, PatchError::Operation(0, "can't move a value into itself")),
+        (r#"[{"op": "add", "path": "/b/5", "value": 1}]"#, PatchError::Path(0, PathError::Missing(BucketPath::parse("/b/5").unwrap()))),
+        (r#"[{"op": "add", "path": "/x", "value": 1}, {"op": "replace", "path": "/y", "value": 1}]"#, PatchError::Path(1, PathError::Missing(BucketPath::parse("/y").unwrap()))),
+        (r#"[{"op": "test", "path": "/b/0", "value": 1.5}]"#, PatchError::Test(0, BucketPath::parse("/b/0").unwrap())),
+    ];
+    for (ops, error) in cases {
+        let mut doc = bucket(r#"{"a": {"b": 1}, "b": [1, 2]}"#);
+        assert_eq!(doc.apply_patch(&patch(ops)), Err(error), "{ops}");
+        assert_eq!(doc, bucket(r#"{"a": {"b": 1}, "b": [1, 2]}"#));
+    }
+    let mut doc = bucket(r#"{"a": {"b": 1}, "b": [1, 2]}"#);
+    doc.apply_patch(&patch(r#"[{"op": "test", "path": "/b/0", "value": 1}, {"op": "move", "from": "/a", "path": "/a"}]"#)).unwrap();
+    // numbers are equal by value, as in RFC 6902
+    doc.apply_patch(&patch(r#"[{"op": "test", "path": "/b", "value": [1.0, 2e0]}, {"op": "test", "path": "/a/b", "value": 1.0}]"#)).unwrap();
+    assert!(matches!(bucket("null").apply_patch(&patch(r#"[{"op": "remove", "path": "/a"}]"#)), Err(PatchError::Path(0, _))));
+}
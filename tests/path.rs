@@ -0,0 +1,101 @@
+use std::collections::BTreeMap;
+use serde::Serialize;
+use serde_bucket::{to_bucket, Bucket, BucketPath, PathError, PathSegment};
+
+#[derive(Serialize)]
+struct Order {
+    id: u32,
+    lines: Vec<Line>,
+    state: State,
+}
+
+#[derive(Serialize)]
+struct Line {
+    sku: &'static str,
+    count: u8,
+}
+
+#[derive(Serialize)]
+enum State {
+    Shipped { carrier: &'static str },
+}
+
+fn key(key: &str) -> PathSegment {
+    PathSegment::Key(key.to_owned())
+}
+
+#[test]
+fn dotted() {
+    let path = BucketPath::parse(r#"a.b[3]["x.y"]["q\"z"].c"#).unwrap();
+    assert_eq!(path.segments(), [key("a"), key("b"), PathSegment::Index(3), key("x.y"), key("q\"z"), key("c")]);
+    assert_eq!(path.to_string(), r#"a.b[3]["x.y"]["q\"z"].c"#);
+    assert_eq!(path.to_pointer(), "/a/b/3/x.y/q\"z/c");
+    assert_eq!(BucketPath::parse("[0].a").unwrap().segments(), [PathSegment::Index(0), key("a")]);
+    // only the first character decides the syntax, so slashes later on are part of a key
+    assert_eq!(BucketPath::parse("a/b").unwrap().segments(), [key("a/b")]);
+    assert_eq!(BucketPath::parse("0").unwrap().segments(), [key("0")]);
+    assert_eq!(BucketPath::parse("").unwrap(), BucketPath::root());
+}
+
+#[test]
+fn pointer() {
+    let path = BucketPath::parse("/a~1b/c~0/3/x.y").unwrap();
+    // pointer tokens are always keys, which are matched against sequence indices when looked up
+    assert_eq!(path.segments(), [key("a/b"), key("c~"), key("3"), key("x.y")]);
+    assert_eq!(path.to_pointer(), "/a~1b/c~0/3/x.y");
+    assert_eq!(path.to_string(), r#"a/b.c~.3["x.y"]"#);
+    assert_eq!(BucketPath::parse("/").unwrap().segments(), [key("")]);
+    assert_eq!(BucketPath::parse("//").unwrap().segments(), [key(""), key("")]);
+    assert_eq!(BucketPath::from_pointer("").unwrap(), BucketPath::root());
+}
+
+#[test]
+fn malformed() {
+    let cases = [
+        (".a", 0), ("a..b", 2), ("a.", 2), ("a]", 1), ("a[", 2), ("a[x]", 2), ("a[01]", 2), ("a[-1]", 2),
+        ("a[1]b", 4), (r#"a["x]"#, 2), (r#"a["x"y"#, 5), ("/a~2", 2), ("/a/b~", 4), ("/~", 1),
+    ];
+    for (path, offset) in cases {
+        match BucketPath::parse(path) {
+            Err(PathError::Syntax(error, at)) => assert_eq!((error.as_str(), at), (path, offset)),
+            result => panic!("{path}: {result:?}"),
+        }
+    }
+    assert!(matches!(BucketPath::from_pointer("a/b"), Err(PathError::Syntax(_, 0))));
+    assert_eq!(BucketPath::parse("a[x]").unwrap_err().to_string(), "invalid path `a[x]` at offset 2");
+}
+
+#[test]
+fn lookup() {
+    let bucket: Bucket = serde_json::from_str(r#"{"a": {"b": [0, 1, {"c": "deep"}]}, "k/x": 5, "k.y": 6}"#).unwrap();
+    assert_eq!(bucket.get("a.b[2].c").unwrap().as_str(), Some("deep"));
+    assert_eq!(bucket.get("/a/b/2/c").unwrap().as_str(), Some("deep"));
+    assert_eq!(bucket.get("/k~1x").unwrap().as_u64(), Some(5));
+    assert_eq!(bucket.get(r#"["k.y"]"#).unwrap().as_u64(), Some(6));
+    assert_eq!(bucket.get("/k.y").unwrap().as_u64(), Some(6));
+    assert_eq!(bucket.get_into::<u8, serde_json::Error>("a.b[1]").unwrap(), 1);
+
+    let error = bucket.get("a.b[9].c").unwrap_err();
+    assert_eq!(error, PathError::Missing(BucketPath::parse("a.b[9]").unwrap()));
+    assert_eq!(error.to_string(), "no value at path `a.b[9]`");
+    assert!(matches!(bucket.get("/a/b/01"), Err(PathError::Missing(_))));
+    assert!(matches!(bucket.get("a.b.c"), Err(PathError::Missing(_))));
+    assert!(matches!(bucket.get("a..b"), Err(PathError::Syntax(_, 2))));
+    let error = bucket.get_into::<u8, serde_json::Error>("a.x").unwrap_err();
+    assert!(error.to_string().contains("no value at path `a.x`"), "{error}");
+}
+
+#[test]
+fn captured() {
+    let order = Order { id: 1, lines: vec![Line { sku: "a", count: 2 }, Line { sku: "b", count: 3 }], state: State::Shipped { carrier: "post" } };
+    let bucket = to_bucket::<_, serde_json::Error>(&order).unwrap();
+    assert_eq!(bucket.get("lines[1].sku").unwrap().as_str(), Some("b"));
+    assert_eq!(bucket.get("/lines/0/count").unwrap().as_u64(), Some(2));
+    assert_eq!(bucket.get("state.Shipped.carrier").unwrap().as_str(), Some("post"));
+    assert_eq!(bucket.get("/state/Shipped/carrier").unwrap().as_str(), Some("post"));
+
+    let map = BTreeMap::from([(7u32, "seven")]);
+    let bucket = to_bucket::<_, serde_json::Error>(&map).unwrap();
+    assert_eq!(bucket.get("[7]").unwrap().as_str(), Some("seven"));
+    assert_eq!(bucket.get("/7").unwrap().as_str(), Some("seven"));
+}
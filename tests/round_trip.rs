@@ -0,0 +1,98 @@
+use std::collections::BTreeMap;
+use serde::{Deserialize, Serialize, Serializer};
+use serde_bucket::{to_bucket, Bucket};
+
+/// bytes, serialized with `serialize_bytes`
+#[derive(Debug, PartialEq)]
+struct Bytes(Vec<u8>);
+
+impl Serialize for Bytes {
+    fn serialize<S>(&self, serializer: S) -> Result<S::Ok, S::Error> where S: Serializer {
+        serializer.serialize_bytes(&self.0)
+    }
+}
+
+#[test]
+fn json_round_trip() {
+    let input = r#"{"bool":true,"int":-7,"big":18446744073709551615,"float":1.5,"null":null,"seq":[1,[2,[3,[]]],{"a":{}}],"map":{"nested":{"deeper":[{"x":"y"}]}}}"#;
+    let bucket: Bucket = serde_json::from_str(input).unwrap();
+    assert_eq!(serde_json::to_string(&bucket).unwrap(), input);
+}
+
+#[test]
+fn escaped_strings() {
+    let input = r#"["quote \" slash \\ newline \n tab \t","é 😀 \u0001",{"key \"with\" quotes":"\\"}]"#;
+    let bucket: Bucket = serde_json::from_str(input).unwrap();
+    assert_eq!(serde_json::to_string(&bucket).unwrap(), input);
+    let value: Vec<serde_json::Value> = serde_json::from_str(input).unwrap();
+    assert_eq!(serde_json::to_string(&bucket).unwrap(), serde_json::to_string(&value).unwrap());
+}
+
+#[test]
+fn bytes() {
+    let bucket: Bucket = to_bucket::<_, serde_json::Error>(&Bytes(vec![0, 1, 255])).unwrap();
+    assert_eq!(serde_json::to_string(&bucket).unwrap(), "[0,1,255]");
+    // serializing a bucket into another keeps every node, including bytes
+    let copy: Bucket = to_bucket::<_, serde_json::Error>(&bucket).unwrap();
+    assert_eq!(copy, bucket);
+}
+
+#[derive(Debug, PartialEq, Serialize, Deserialize)]
+struct Scalars {
+    a: u8,
+    b: i16,
+    c: u32,
+    d: i64,
+    e: u128,
+    f: i128,
+    g: f32,
+    h: f64,
+    i: bool,
+    j: char,
+    k: String,
+    l: (),
+}
+
+fn scalars() -> Scalars {
+    Scalars {
+        a: u8::MAX,
+        b: i16::MIN,
+        c: 7,
+        d: -1,
+        e: u128::MAX,
+        f: i128::MIN,
+        g: 0.5,
+        h: -1e300,
+        i: true,
+        j: 'é',
+        k: "line\nbreak \"quoted\" \\".to_owned(),
+        l: (),
+    }
+}
+
+#[test]
+fn typed_scalars() {
+    let mut bucket: Bucket = to_bucket::<_, serde_json::Error>(&scalars()).unwrap();
+    assert_eq!(bucket.deserialize_into_clone::<Scalars, serde_json::Error>().unwrap(), scalars());
+    assert_eq!(bucket.deserialize_into::<Scalars, serde_json::Error>().unwrap(), scalars());
+}
+
+type Nested = Vec<BTreeMap<String, Vec<(u8, Option<i32>)>>>;
+
+#[test]
+fn typed_containers() {
+    let value: Nested = vec![
+        BTreeMap::from([("a".to_owned(), vec![(1, Some(-1)), (2, None)]), ("b".to_owned(), vec![])]),
+        BTreeMap::new(),
+    ];
+    let mut bucket: Bucket = to_bucket::<_, serde_json::Error>(&value).unwrap();
+    assert_eq!(bucket.deserialize_into_clone::<Nested, serde_json::Error>().unwrap(), value);
+    assert_eq!(bucket.deserialize_into::<Nested, serde_json::Error>().unwrap(), value);
+}
+
+#[test]
+fn typed_bytes() {
+    let bucket: Bucket = to_bucket::<_, serde_json::Error>(&Bytes(vec![0, 1, 255])).unwrap();
+    let bytes: &[u8] = bucket.deserialize_into_clone::<&[u8], serde_json::Error>().unwrap();
+    assert_eq!(bytes, [0, 1, 255]);
+}
@@ -0,0 +1,56 @@
+use serde::de::DeserializeSeed;
+use serde::Deserialize;
+use serde_bucket::{Bucket, BucketLimits, OwnedBucket};
+
+#[derive(Debug, PartialEq, Deserialize)]
+struct Message {
+    id: u32,
+    body: String,
+}
+
+#[test]
+fn reuse_owned() {
+    let mut bucket = OwnedBucket::from(Bucket::with_capacity(16));
+    let mut buffer = String::new();
+    for id in 0..3 {
+        buffer.clear();
+        buffer.push_str(&format!(r#"{{"id": {}, "body": "hello\n"}}"#, id));
+        bucket.seed().deserialize(&mut serde_json::Deserializer::from_str(&buffer)).unwrap();
+        let message = bucket.deserialize_into_clone::<Message, serde_json::Error>().unwrap();
+        assert_eq!(message, Message { id, body: "hello\n".to_owned() });
+    }
+}
+
+#[test]
+fn reuse_borrowed() {
+    let inputs = [r#"{"id": 1, "body": "a"}"#, r#"[2, "b"]"#];
+    let mut bucket = Bucket::with_capacity(16);
+    bucket.set_coerce(true);
+    for (id, input) in inputs.iter().enumerate() {
+        bucket.seed().deserialize(&mut serde_json::Deserializer::from_str(input)).unwrap();
+        assert_eq!(bucket.deserialize_into_clone::<Message, serde_json::Error>().unwrap().id, id as u32 + 1);
+    }
+    let limits = BucketLimits { max_nodes: 2, ..BucketLimits::default() };
+    assert!(bucket.seed().with_limits(limits).deserialize(&mut serde_json::Deserializer::from_str(inputs[1])).is_err());
+    bucket.clear();
+    assert!(bucket.deserialize_into_clone::<Message, serde_json::Error>().is_err());
+}
+
+#[test]
+fn failed_capture_is_cleared() {
+    let limits = BucketLimits { max_nodes: 3, ..BucketLimits::default() };
+    let mut bucket = Bucket::with_capacity(16);
+    let mut input = serde_json::Deserializer::from_str(r#"{"id": 1, "body": "a"}"#);
+    assert!(bucket.seed().with_limits(limits).deserialize(&mut input).is_err());
+    assert_eq!(bucket, Bucket::with_capacity(0));
+    let error = bucket.deserialize_into_clone::<Message, serde_json::Error>().unwrap_err();
+    assert_eq!(error.to_string(), "out-of-bounds at node 0");
+
+    let mut owned = OwnedBucket::from(Bucket::with_capacity(16));
+    let mut input = serde_json::Deserializer::from_str(r#"{"id": 1, "body": [1, 2, "#);
+    assert!(owned.seed().deserialize(&mut input).is_err());
+    assert_eq!(*owned, Bucket::with_capacity(0));
+    // and the bucket can still be reused
+    owned.seed().deserialize(&mut serde_json::Deserializer::from_str(r#"[3, "c"]"#)).unwrap();
+    assert_eq!(owned.deserialize_into_clone::<Message, serde_json::Error>().unwrap(), Message { id: 3, body: "c".to_owned() });
+}
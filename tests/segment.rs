@@ -0,0 +1,47 @@
+use serde::Deserialize;
+use serde_bucket::Bucket;
+
+#[derive(Debug, PartialEq, Deserialize)]
+struct Payload {
+    id: u32,
+}
+
+const INPUT: &str = r#"{"type": "thing", "payload": {"id": 7}, "list": [1, [2, 3], {"x": 4}, 5]}"#;
+
+#[test]
+fn indexing() {
+    let mut bucket: Bucket = serde_json::from_str(INPUT).unwrap();
+    let kind = bucket.segment().map_get("type").unwrap().deserialize_into_clone::<String, serde_json::Error>().unwrap();
+    assert_eq!(kind, "thing");
+    let payload = bucket.segment().map_get("payload").unwrap().deserialize_into::<Payload, serde_json::Error>().unwrap();
+    assert_eq!(payload, Payload { id: 7 });
+    let last = bucket.segment().map_get("list").unwrap().seq_index(3).unwrap().deserialize_into::<u8, serde_json::Error>().unwrap();
+    assert_eq!(last, 5);
+    let x = bucket.segment().map_get("list").unwrap().seq_index(2).unwrap().map_get("x").unwrap().deserialize_into::<u8, serde_json::Error>().unwrap();
+    assert_eq!(x, 4);
+
+    assert!(bucket.segment().map_get("list").unwrap().seq_index(4).is_none());
+    assert!(bucket.segment().map_get("missing").is_none());
+    assert!(bucket.segment().seq_index(0).is_none());
+    assert!(bucket.segment().map_get("type").unwrap().map_get("x").is_none());
+}
+
+#[test]
+fn iterating() {
+    let mut bucket: Bucket = serde_json::from_str(INPUT).unwrap();
+    let keys: Vec<String> = bucket.segment().iter_map().unwrap()
+        .map(|(key, _)| key.deserialize_into_clone::<String, serde_json::Error>().unwrap())
+        .collect();
+    assert_eq!(keys, ["type", "payload", "list"]);
+
+    // segments are disjoint, so can be deserialized in any order
+    let mut elements: Vec<_> = bucket.segment().map_get("list").unwrap().iter_seq().unwrap().collect();
+    assert_eq!(elements.len(), 4);
+    let pair = elements[1].deserialize_into::<Vec<u8>, serde_json::Error>().unwrap();
+    let first = elements[0].deserialize_into::<u8, serde_json::Error>().unwrap();
+    assert_eq!((first, pair), (1, vec![2, 3]));
+
+    // the rest of the bucket is untouched
+    let payload = bucket.segment().map_get("payload").unwrap().deserialize_into::<Payload, serde_json::Error>().unwrap();
+    assert_eq!(payload, Payload { id: 7 });
+}
@@ -0,0 +1,45 @@
+use std::thread;
+use serde::Deserialize;
+use serde_bucket::Bucket;
+
+#[derive(Debug, PartialEq, Deserialize)]
+struct Borrowed<'a> {
+    name: &'a str,
+    count: u8,
+}
+
+#[derive(Debug, PartialEq, Deserialize)]
+struct Owned {
+    name: String,
+    tags: Vec<String>,
+}
+
+// the escaped string is captured as an owned `String`
+const INPUT: &str = r#"{"name": "a\"b", "count": 1, "tags": ["x", "y\n"]}"#;
+
+#[test]
+fn repeated() {
+    let bucket: Bucket = serde_json::from_str(INPUT).unwrap();
+    for _ in 0..3 {
+        let borrowed = bucket.deserialize_into_clone::<Borrowed, serde_json::Error>().unwrap();
+        assert_eq!(borrowed, Borrowed { name: "a\"b", count: 1 });
+        let owned = bucket.deserialize_into_clone::<Owned, serde_json::Error>().unwrap();
+        assert_eq!(owned, Owned { name: "a\"b".to_owned(), tags: vec!["x".to_owned(), "y\n".to_owned()] });
+    }
+    assert_eq!(serde_json::to_string(&bucket).unwrap(), r#"{"name":"a\"b","count":1,"tags":["x","y\n"]}"#);
+}
+
+#[test]
+fn multithreaded() {
+    let bucket: Bucket = serde_json::from_str(INPUT).unwrap();
+    thread::scope(|scope| {
+        let threads: Vec<_> = (0..8)
+            .map(|_| scope.spawn(|| bucket.deserialize_into_clone::<Borrowed, serde_json::Error>().unwrap()))
+            .collect();
+        for thread in threads {
+            assert_eq!(thread.join().unwrap(), Borrowed { name: "a\"b", count: 1 });
+        }
+    });
+    let owned = bucket.into_owned();
+    assert_eq!(owned.deserialize_into_clone::<Borrowed, serde_json::Error>().unwrap().name, "a\"b");
+}
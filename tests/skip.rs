@@ -0,0 +1,36 @@
+use serde::Deserialize;
+use serde_bucket::Bucket;
+
+#[derive(Debug, PartialEq, Deserialize)]
+struct Last {
+    x: u8,
+}
+
+#[test]
+fn nested_values() {
+    let input = r#"{"a": [1, [2, {"q": ["s", []]}], []], "b": {"c": {"d": [[[]]]}}, "x": 5}"#;
+    let mut bucket: Bucket = serde_json::from_str(input).unwrap();
+    assert_eq!(bucket.deserialize_into_clone::<Last, serde_json::Error>().unwrap(), Last { x: 5 });
+    let x = bucket.segment().map_get("x").unwrap().deserialize_into::<u8, serde_json::Error>().unwrap();
+    assert_eq!(x, 5);
+    let q = bucket.segment().map_get("a").unwrap().seq_index(1).unwrap().seq_index(1).unwrap().map_get("q").unwrap()
+        .deserialize_into::<(String, Vec<u8>), serde_json::Error>().unwrap();
+    assert_eq!(q, ("s".to_owned(), vec![]));
+    assert_eq!(bucket.get("a[2]").unwrap().len(), Some(0));
+
+    let deep = format!("[{}{}, 7]", "[".repeat(100), "]".repeat(100));
+    let bucket: Bucket = serde_json::from_str(&deep).unwrap();
+    assert_eq!(bucket.get("[1]").unwrap().as_u64(), Some(7));
+}
+
+#[test]
+fn large_values() {
+    // skipping uses the span of a container, so doesn't walk it's elements
+    let big: Vec<Vec<u32>> = (0..1000).map(|i| (0..100).map(|j| i * j).collect()).collect();
+    let input = format!(r#"{{"big": {}, "x": 1}}"#, serde_json::to_string(&big).unwrap());
+    let mut bucket: Bucket = serde_json::from_str(&input).unwrap();
+    for _ in 0..10_000 {
+        assert!(bucket.segment().map_get("x").is_some());
+        assert_eq!(bucket.deserialize_into_clone::<Last, serde_json::Error>().unwrap(), Last { x: 1 });
+    }
+}
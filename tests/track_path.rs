@@ -0,0 +1,63 @@
+use std::borrow::Cow;
+use std::fmt::{Display, Formatter};
+use serde::Deserialize;
+use serde_bucket::Bucket;
+
+#[derive(Debug, Deserialize)]
+#[allow(dead_code)]
+struct Order {
+    items: Vec<Item>,
+}
+
+#[derive(Debug, Deserialize)]
+#[allow(dead_code)]
+struct Item {
+    price: u32,
+}
+
+const INPUT: &str = r#"{"items": [{"price": 1}, {"price": "x"}]}"#;
+
+#[test]
+fn untracked_by_default() {
+    let bucket: Bucket = serde_json::from_str(INPUT).unwrap();
+    let error = bucket.deserialize_into_clone::<Order, serde_json::Error>().unwrap_err();
+    assert_eq!(error.to_string(), "invalid type: string \"x\", expected u32");
+}
+
+#[test]
+fn tracked() {
+    let mut bucket: Bucket = serde_json::from_str(INPUT).unwrap();
+    bucket.set_track_path(true);
+    let error = bucket.deserialize_into_clone::<Order, serde_json::Error>().unwrap_err();
+    assert_eq!(error.to_string(), "invalid type: string \"x\", expected u32 at items[1].price");
+    let error = bucket.get_into::<Item, serde_json::Error>("items[1]").unwrap_err();
+    assert_eq!(error.to_string(), "invalid type: string \"x\", expected u32 at items[1].price");
+}
+
+/// an error borrowing it's message, so isn't `'static`
+#[derive(Debug)]
+struct BorrowedError<'a>(Cow<'a, str>);
+
+impl Display for BorrowedError<'_> {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        f.write_str(&self.0)
+    }
+}
+
+impl std::error::Error for BorrowedError<'_> {}
+
+impl serde::de::Error for BorrowedError<'_> {
+    fn custom<T>(msg: T) -> Self where T: Display {
+        Self(Cow::Owned(msg.to_string()))
+    }
+}
+
+#[test]
+fn non_static_error() {
+    let mut bucket: Bucket = serde_json::from_str(INPUT).unwrap();
+    bucket.set_track_path(true);
+    let error = bucket.deserialize_into_clone::<Order, BorrowedError>().unwrap_err();
+    assert_eq!(error.to_string(), "invalid type: string \"x\", expected u32 at items[1].price");
+    let error = bucket.get_into::<Item, BorrowedError>("items[2]").unwrap_err();
+    assert_eq!(error.to_string(), "no value at path `items[2]`");
+}
@@ -0,0 +1,33 @@
+use serde::Deserialize;
+use serde_bucket::Bucket;
+
+#[derive(Debug, PartialEq, Deserialize)]
+struct Meters(u32);
+
+#[derive(Debug, PartialEq, Deserialize)]
+struct Point {
+    x: i8,
+    y: i8,
+}
+
+fn bucket(input: &str) -> Bucket<'_> {
+    serde_json::from_str(input).unwrap()
+}
+
+#[test]
+fn typed_values() {
+    assert_eq!(bucket("5").deserialize_into_clone::<Meters, serde_json::Error>().unwrap(), Meters(5));
+    assert_eq!(bucket("[1, 2]").deserialize_into_clone::<Point, serde_json::Error>().unwrap(), Point { x: 1, y: 2 });
+}
+
+#[test]
+fn mismatches() {
+    let error = bucket(r#""true""#).deserialize_into_clone::<bool, serde_json::Error>().unwrap_err();
+    assert_eq!(error.to_string(), "invalid type: string \"true\", expected a boolean");
+    let error = bucket("[1]").deserialize_into_clone::<Point, serde_json::Error>().unwrap_err();
+    assert_eq!(error.to_string(), "invalid length 1, expected struct Point with 2 elements");
+    let error = bucket("[1, 2, 3]").deserialize_into_clone::<(u8, u8), serde_json::Error>().unwrap_err();
+    assert_eq!(error.to_string(), "invalid length 3, expected 2 elements in sequence");
+    let error = bucket("300").deserialize_into_clone::<u8, serde_json::Error>().unwrap_err();
+    assert_eq!(error.to_string(), "invalid value: integer `300`, expected u8");
+}
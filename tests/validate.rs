@@ -0,0 +1,39 @@
+#![cfg(feature = "serializer")]
+
+use serde::{Serialize, Serializer};
+use serde::ser::{Error, SerializeSeq};
+use serde_bucket::{to_bucket, Bucket};
+
+/// a sequence that fails part-way through serializing
+struct Failing;
+
+impl Serialize for Failing {
+    fn serialize<S>(&self, serializer: S) -> Result<S::Ok, S::Error> where S: Serializer {
+        let mut seq = serializer.serialize_seq(Some(3))?;
+        seq.serialize_element(&1u8)?;
+        Err(S::Error::custom("failed"))
+    }
+}
+
+fn failed_capture() -> Bucket<'static> {
+    let mut bucket = to_bucket::<_, serde_json::Error>(&0u8).unwrap();
+    assert!(Failing.serialize(&mut bucket.serializer::<serde_json::Error>()).is_err());
+    bucket
+}
+
+#[test]
+fn well_formed() {
+    let bucket: Bucket = serde_json::from_str(r#"{"a": [1, {"b": null}], "c": "d"}"#).unwrap();
+    assert!(bucket.validate::<serde_json::Error>().is_ok());
+    let bucket = to_bucket::<_, serde_json::Error>(&(Some(1u8), vec!["a", "b"])).unwrap();
+    assert!(bucket.validate::<serde_json::Error>().is_ok());
+}
+
+#[test]
+fn failed_capture_is_invalid() {
+    let mut bucket = failed_capture();
+    assert!(bucket.validate::<serde_json::Error>().is_err());
+    assert!(bucket.deserialize_into::<Vec<u8>, serde_json::Error>().is_err());
+    assert!(serde_json::to_string(&bucket).is_err());
+    let _ = format!("{:?}", bucket);
+}
@@ -0,0 +1,100 @@
+use serde::{Deserialize, Serialize};
+use serde_bucket::{to_bucket, Bucket, BucketKind, ValueRef};
+
+#[derive(Debug, PartialEq, Serialize, Deserialize)]
+struct Profile {
+    name: String,
+    tags: Vec<u32>,
+    level: Level,
+}
+
+#[derive(Debug, PartialEq, Serialize, Deserialize)]
+enum Level {
+    Low,
+    Mid(u8),
+    High { x: i8 },
+}
+
+#[test]
+fn accessors() {
+    let input = r#"{"a": [1, -2, 3.5, "x", null, true], "b": {"c": "d"}, "level": {"Mid": 3}}"#;
+    let bucket: Bucket = serde_json::from_str(input).unwrap();
+    let view = bucket.view();
+    assert_eq!(view.kind(), BucketKind::Map);
+    assert_eq!(view.len(), Some(3));
+    let a = view.map_get("a").unwrap();
+    assert_eq!(a.len(), Some(6));
+    assert_eq!(a.seq_index(0).unwrap().as_u64(), Some(1));
+    assert_eq!(a.seq_index(1).unwrap().as_i64(), Some(-2));
+    assert_eq!(a.seq_index(1).unwrap().as_u64(), None);
+    assert_eq!(a.seq_index(2).unwrap().as_f64(), Some(3.5));
+    assert_eq!(a.seq_index(3).unwrap().as_str(), Some("x"));
+    assert!(a.seq_index(4).unwrap().is_null());
+    assert_eq!(a.seq_index(5).unwrap().as_bool(), Some(true));
+    assert!(a.seq_index(6).is_none());
+    assert_eq!(view.map_get("b").unwrap().map_get("c").unwrap().as_str(), Some("d"));
+    match view.map_get("level").unwrap().value() {
+        ValueRef::Map(map) => {
+            let (key, value) = map.iter().next().unwrap();
+            assert_eq!(key.as_str(), Some("Mid"));
+            assert_eq!(value.as_u64(), Some(3));
+        }
+        other => panic!("{other:?}"),
+    }
+    let level: Level = view.map_get("level").unwrap().deserialize_into_clone::<Level, serde_json::Error>().unwrap();
+    assert_eq!(level, Level::Mid(3));
+    let keys: Vec<_> = view.as_map().unwrap().iter().map(|(key, _)| key.as_str().unwrap()).collect();
+    assert_eq!(keys, ["a", "b", "level"]);
+}
+
+#[test]
+fn captured() {
+    let profile = Profile { name: "n".into(), tags: vec![1, 2], level: Level::High { x: -1 } };
+    let bucket: Bucket = to_bucket::<_, serde_json::Error>(&profile).unwrap();
+    let view = bucket.view();
+    match view.value() {
+        ValueRef::Struct("Profile", fields) => {
+            assert_eq!(fields.len(), 3);
+            assert_eq!(fields.get("name").unwrap().as_str(), Some("n"));
+            let tags: Vec<_> = fields.get("tags").unwrap().as_seq().unwrap().iter().map(|tag| tag.as_u64().unwrap()).collect();
+            assert_eq!(tags, [1, 2]);
+            match fields.get("level").unwrap().value() {
+                ValueRef::Variant("Level", 2, "High", Some(payload)) => assert_eq!(payload.map_get("x").unwrap().as_i64(), Some(-1)),
+                other => panic!("{other:?}"),
+            }
+        }
+        other => panic!("{other:?}"),
+    }
+    assert_eq!(view.len(), Some(3));
+    let low: Bucket = to_bucket::<_, serde_json::Error>(&Level::Low).unwrap();
+    assert!(matches!(low.view().value(), ValueRef::Variant("Level", 0, "Low", None)));
+    assert_eq!(low.view().len(), None);
+    let back: Profile = view.deserialize_into_clone::<Profile, serde_json::Error>().unwrap();
+    assert_eq!(back, profile);
+    let mut bucket = bucket;
+    let mut segment = bucket.segment();
+    assert_eq!(segment.view().kind(), BucketKind::Struct);
+    let _: Profile = segment.deserialize_into::<Profile, serde_json::Error>().unwrap();
+    assert_eq!(bucket.view().map_get("name").unwrap().kind(), BucketKind::Empty);
+}
+
+#[test]
+fn widened_values() {
+    let bucket: Bucket = to_bucket::<_, serde_json::Error>(&(7u8, -7i16, 0.5f32, 'c', Some(()), u128::MAX)).unwrap();
+    let values: Vec<ValueRef> = bucket.view().as_seq().unwrap().iter().map(|value| value.value()).collect();
+    assert!(matches!(values[..], [
+        ValueRef::UInt(7),
+        ValueRef::Int(-7),
+        ValueRef::Float(val),
+        ValueRef::Char('c'),
+        ValueRef::Some(unit),
+        ValueRef::UInt(u128::MAX),
+    ] if val == 0.5 && unit.kind() == BucketKind::Unit));
+    let tuple = bucket.view();
+    assert_eq!(tuple.seq_index(0).unwrap().as_i64(), Some(7));
+    assert_eq!(tuple.seq_index(1).unwrap().as_f64(), Some(-7.0));
+    assert_eq!(tuple.seq_index(1).unwrap().as_u64(), None);
+    assert_eq!(tuple.seq_index(2).unwrap().as_f64(), Some(0.5));
+    assert_eq!(tuple.seq_index(5).unwrap().as_u64(), None);
+    assert_eq!(tuple.seq_index(3).unwrap().as_str(), None);
+}